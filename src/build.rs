@@ -1,10 +1,32 @@
+use crate::{
+    cargo_config,
+    codegen::RustCodegen,
+    cpu::{translate_arch_cpu, translate_arch_feature, translate_target_cpu_model},
+    diagnostic::parse_diagnostics,
+    jobserver::{parse_makeflags, Jobserver},
+    manifest,
+    memory::memory_limit,
+    options::{closest_option, parse_project_options},
+    output::{killed_by_sigkill, run_command},
+    progress::{inherit_progress_pipe, progress_pipe, report_progress, ProgressCallback},
+    step::parse_steps,
+    target::{guess_target_triple, translate_target_triple},
+    target_spec::TargetSpec,
+    toml::Value,
+    BuildStep, BuildSummary, CodegenOption, Cpu, Error, OsVersion, OsVersionRange, OutputMode,
+    Progress, ProjectOption, Result, Severity, SummaryMode, Target,
+};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     env,
     ffi::{OsStr, OsString},
+    fmt::Display,
+    fs,
     io::ErrorKind,
     path::{Path, PathBuf},
     process::Command,
+    thread,
+    time::Duration,
 };
 
 /// Zig build release modes.
@@ -28,10 +50,63 @@ pub enum Optimize {
     ReleaseSmall,
 }
 
+impl ReleaseMode {
+    /// Parses the name of a release mode, as accepted by `zig build --release`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "any" | "auto" => Some(ReleaseMode::Auto),
+            "fast" => Some(ReleaseMode::Fast),
+            "safe" => Some(ReleaseMode::Safe),
+            "small" => Some(ReleaseMode::Small),
+            _ => None,
+        }
+    }
+}
+
+impl Optimize {
+    /// Parses the name of an optimization modus, as accepted by `zig build -Doptimize`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Default" => Some(Optimize::Default),
+            "Debug" => Some(Optimize::Debug),
+            "ReleaseSafe" => Some(Optimize::ReleaseSafe),
+            "ReleaseFast" => Some(Optimize::ReleaseFast),
+            "ReleaseSmall" => Some(Optimize::ReleaseSmall),
+            _ => None,
+        }
+    }
+}
+
+/// Release mode and optimization modus configured for a Rust profile.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct ProfileMode {
+    pub(crate) release: Option<ReleaseMode>,
+    pub(crate) optimize: Option<Optimize>,
+}
+
+/// Results of a successful Zig build.
+#[derive(Debug, Clone)]
+pub struct Artifacts {
+    /// Prefix path the package was installed to.
+    pub prefix: PathBuf,
+    /// Summary of the build, unless disabled with [`SummaryMode::None`].
+    pub summary: Option<BuildSummary>,
+}
+
+/// Configuration of the Cargo features forwarded to the build.
+#[derive(Debug, Clone, Default)]
+struct CargoFeatures {
+    prefix: String,
+    allow: Option<BTreeSet<String>>,
+    list: Option<String>,
+}
+
 /// Builder style configuration for a pending Zig build.
 pub struct Build {
     path: PathBuf,
-    step: Option<OsString>,
+    package_name: Option<String>,
+    steps: Vec<OsString>,
+    run_args: Vec<OsString>,
     // General options.
     prefix: Option<PathBuf>,
     prefix_lib_dir: Option<PathBuf>,
@@ -47,15 +122,26 @@ pub struct Build {
     verbose: bool,
     prominent_compile_errors: bool,
     jobs: Option<usize>,
+    jobserver: bool,
     maxrss: Option<usize>,
     skip_oom_steps: bool,
+    auto_maxrss: bool,
     incremental: Option<bool>,
     // Project-specific options.
     target: Option<OsString>,
-    cpu: Option<OsString>,
+    target_mappings: HashMap<String, Target>,
+    target_spec: Option<PathBuf>,
+    cpu: Option<Cpu>,
     dynamic_linker: Option<PathBuf>,
     optimize: Option<Optimize>,
+    codegen_optimize: bool,
+    codegen_options: Vec<(CodegenOption, String)>,
+    cargo_features: Option<CargoFeatures>,
+    cargo_config: Option<String>,
+    profiles: HashMap<String, ProfileMode>,
     options: Vec<OsString>,
+    link_libs: Vec<String>,
+    rerun_if_changed: Vec<PathBuf>,
     // Advanced options.
     reference_trace: Option<usize>,
     no_reference_trace: bool,
@@ -72,8 +158,19 @@ pub struct Build {
     verbose_cimport: bool,
     verbose_cc: bool,
     verbose_llvm_cpu_features: bool,
+    summary: SummaryMode,
+    summary_json: bool,
+    output: OutputMode,
+    error_output_lines: usize,
+    progress_callback: Option<ProgressCallback>,
+    progress_interval: Duration,
     // Additional members.
     env_cache: HashMap<String, Option<OsString>>,
+    env_listed: bool,
+    build_help: Option<(Vec<OsString>, Option<String>)>,
+    manifest_metadata: Option<Option<Value>>,
+    llvm_versions_checked: bool,
+    rerun_directives: Vec<String>,
 }
 
 impl Build {
@@ -82,7 +179,9 @@ impl Build {
     pub fn new(path: impl AsRef<Path>) -> Self {
         Self {
             path: env::current_dir().unwrap().join(path),
-            step: None,
+            package_name: None,
+            steps: vec![],
+            run_args: vec![],
             prefix: None,
             prefix_lib_dir: None,
             prefix_exe_dir: None,
@@ -97,14 +196,25 @@ impl Build {
             verbose: false,
             prominent_compile_errors: false,
             jobs: None,
+            jobserver: true,
             maxrss: None,
             skip_oom_steps: false,
+            auto_maxrss: true,
             incremental: None,
             target: None,
+            target_mappings: Default::default(),
+            target_spec: None,
             cpu: None,
             dynamic_linker: None,
             optimize: None,
+            codegen_optimize: false,
+            codegen_options: vec![],
+            cargo_features: None,
+            cargo_config: None,
+            profiles: Default::default(),
             options: vec![],
+            link_libs: vec![],
+            rerun_if_changed: vec![],
             reference_trace: None,
             no_reference_trace: false,
             build_file: None,
@@ -120,13 +230,104 @@ impl Build {
             verbose_cimport: false,
             verbose_cc: false,
             verbose_llvm_cpu_features: false,
+            summary: SummaryMode::All,
+            summary_json: false,
+            output: OutputMode::Streamed,
+            error_output_lines: 20,
+            progress_callback: None,
+            progress_interval: Duration::from_secs(10),
             env_cache: Default::default(),
+            env_listed: false,
+            build_help: None,
+            manifest_metadata: None,
+            llvm_versions_checked: false,
+            rerun_directives: vec![],
+        }
+    }
+
+    /// Creates the configuration declared in the `[package.metadata.zigcli]` table of the manifest
+    /// of the package being built.
+    ///
+    /// The table supports the `path`, `step`, `steps`, `release`, `optimize`, `codegen-optimize`,
+    /// `target`, `targets`, `cpu`, `jobs`, `options`, `profiles`, `link` and `rerun-if-changed`
+    /// keys, e.g.:
+    ///
+    /// ```toml
+    /// [package.metadata.zigcli]
+    /// path = "libfoo"
+    /// release = "fast"
+    /// options = { shared = false }
+    /// targets = { "x86_64-unknown-linux-gnu" = "x86_64-linux-gnu.2.17" }
+    /// link = ["static=foo"]
+    /// rerun-if-changed = true
+    /// ```
+    ///
+    /// Paths are relative to the directory of the manifest. The returned configuration can still
+    /// be modified before building. Manifests declaring multiple packages must use
+    /// [`Build::from_manifest_package`] instead.
+    pub fn from_manifest() -> Self {
+        Self::manifest_build(None)
+    }
+
+    /// Creates the configuration of the package `name`, declared in the
+    /// `[package.metadata.zigcli.packages.<name>]` table of the manifest.
+    ///
+    /// The keys of the `[package.metadata.zigcli]` table are shared by all packages, and may be
+    /// overridden per package. See [`Build::from_manifest`] for the supported keys.
+    pub fn from_manifest_package(name: &str) -> Self {
+        Self::manifest_build(Some(name))
+    }
+
+    /// Returns the names of the packages declared in the manifest.
+    pub fn manifest_packages() -> Vec<String> {
+        manifest::read_metadata()
+            .map(|metadata| manifest::package_names(&metadata))
+            .unwrap_or_default()
+    }
+
+    fn manifest_build(name: Option<&str>) -> Self {
+        let metadata = match manifest::read_metadata() {
+            Some(metadata) => metadata,
+            None => fail("the manifest has no `[package.metadata.zigcli]` table"),
+        };
+        let config = match manifest::package_config(&metadata, name) {
+            Ok(config) => config,
+            Err(e) => fail(&format!("invalid zigcli metadata in manifest: {}", e)),
+        };
+        let dir = manifest::manifest_dir().unwrap_or_default();
+        // The `path` key is required by `package_config`.
+        let Some(path) = config["path"].as_str() else {
+            fail("invalid zigcli metadata in manifest: `path`: expected a string");
+        };
+        let mut build = Self::new(dir.join(path));
+        build.package_name = name.map(str::to_string);
+        if let Err(e) = manifest::configure(&mut build, &config) {
+            fail(&format!("invalid zigcli metadata in manifest: {}", e));
         }
+        build.manifest_metadata = Some(Some(metadata));
+        build
     }
 
     /// Sets the build step, this will default to `install` if not specified.
     pub fn step(&mut self, step: &str) -> &mut Self {
-        self.step = Some(OsString::from(step));
+        self.steps(Some(step))
+    }
+
+    /// Sets the build steps, replacing the previously set steps.
+    ///
+    /// See [`Build::list_steps`] for the steps declared by the package.
+    pub fn steps(&mut self, steps: impl IntoIterator<Item = impl AsRef<OsStr>>) -> &mut Self {
+        self.steps = steps
+            .into_iter()
+            .map(|s| s.as_ref().to_os_string())
+            .collect();
+        self
+    }
+
+    /// Adds arguments passed to the run steps, i.e. after `--`.
+    pub fn run_args(&mut self, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> &mut Self {
+        self.run_args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
         self
     }
 
@@ -217,6 +418,16 @@ impl Build {
         self
     }
 
+    /// Sets whether the limit of concurrent jobs is sized with the tokens of the jobserver of
+    /// Cargo, this will default to `true` if not specified.
+    ///
+    /// Unless [`Build::jobs`] is set, as many tokens as are available are acquired, up to
+    /// `NUM_JOBS`, and released once the build ends. Without a jobserver, the limit is `NUM_JOBS`.
+    pub fn jobserver(&mut self, enabled: bool) -> &mut Self {
+        self.jobserver = enabled;
+        self
+    }
+
     /// Sets the memory usage limit.
     pub fn maxrss(&mut self, maxrss: usize) -> &mut Self {
         self.maxrss = Some(maxrss);
@@ -229,6 +440,17 @@ impl Build {
         self
     }
 
+    /// Sets whether the memory usage limit is derived from the memory available to the build,
+    /// this will default to `true` if not specified.
+    ///
+    /// Unless [`Build::maxrss`] is set, the limit is the lowest of the cgroup memory limits, and
+    /// zig's default is kept when there is none. Steps which would exceed it fail rather than
+    /// being skipped, unless [`Build::skip_oom_steps`] is set.
+    pub fn auto_maxrss(&mut self, enabled: bool) -> &mut Self {
+        self.auto_maxrss = enabled;
+        self
+    }
+
     /// Sets the incremental compilation option.
     pub fn incremental(&mut self, enabled: bool) -> &mut Self {
         self.incremental = Some(enabled);
@@ -238,49 +460,84 @@ impl Build {
     /// Sets the CPU architecture, OS, and ABI to build for.
     ///
     /// If set, the configuration will not inherit the target and CPU features of the current Rust
-    /// profile.
+    /// profile. The target is passed to zig as is, see [`Target`] to build it from its parts.
     pub fn target(&mut self, target: impl AsRef<OsStr>) -> &mut Self {
         self.target = Some(target.as_ref().into());
         self
     }
 
-    /// Sets the CPU features for the build configuration.
+    /// Maps the Rust target triple `rust` to the zig target `zig`.
+    ///
+    /// Unless a target is set explicitly, the mapping is used instead of the builtin translation of
+    /// the `TARGET` environment variable, e.g. to support custom target triples.
+    ///
+    /// # Panics
+    ///
+    /// The target must be a valid zig target, see [`Target`].
+    pub fn target_mapping<T>(&mut self, rust: impl Into<String>, zig: T) -> &mut Self
+    where
+        T: TryInto<Target>,
+        T::Error: Display,
+    {
+        match zig.try_into() {
+            Ok(zig) => self.target_mappings.insert(rust.into(), zig),
+            Err(e) => panic!("invalid target: {}", e),
+        };
+        self
+    }
+
+    /// Sets the path to the JSON specification of a custom Rust target.
+    ///
+    /// Unless a target is set explicitly, the zig target and CPU are derived from the
+    /// specification. By default, the specification is searched in the working directory and in
+    /// `RUST_TARGET_PATH` when the `TARGET` environment variable names an unknown target.
+    pub fn target_spec(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.target_spec = Some(env::current_dir().unwrap().join(path));
+        self
+    }
+
+    /// Sets the CPU features for the build configuration, e.g. `x86_64_v3+aes`.
+    ///
+    /// If a model is set, the configuration will not inherit the CPU features of the current Rust
+    /// profile. Features alone, e.g. `+aes-avx2`, are applied on top of them, like with
+    /// [`Build::add_cpu`] and [`Build::remove_cpu`].
+    ///
+    /// # Panics
     ///
-    /// If set, the configuration will not inherit the CPU features of the current Rust profile.
+    /// The CPU must be valid, see [`Cpu`].
     pub fn cpu(&mut self, cpu: impl AsRef<OsStr>) -> &mut Self {
-        self.cpu = Some(cpu.as_ref().into());
+        match cpu.as_ref().to_string_lossy().parse() {
+            Ok(cpu) => self.cpu = Some(cpu),
+            Err(e) => panic!("invalid cpu: {}", e),
+        }
         self
     }
 
-    /// Adds a CPU feature from the build configuration.
+    /// Adds a CPU feature to the build configuration.
+    ///
+    /// Unless a model is set with [`Build::cpu`], the feature is applied on top of the CPU
+    /// features of the current Rust profile.
+    ///
+    /// # Panics
     ///
-    /// If set, the configuration will not inherit the CPU features of the current Rust profile.
+    /// See [`Cpu::add`] for the requirements of this method.
     pub fn add_cpu(&mut self, cpu: impl AsRef<OsStr>) -> &mut Self {
-        match self.cpu.as_mut() {
-            None => {
-                self.cpu = Some(cpu.as_ref().into());
-            }
-            Some(x) => {
-                x.push("+");
-                x.push(cpu);
-            }
-        }
+        let feature = cpu.as_ref().to_string_lossy().into_owned();
+        self.cpu.get_or_insert_with(Cpu::default).add(feature);
         self
     }
 
     /// Subtracts a CPU feature from the build configuration.
     ///
-    /// If set, the configuration will not inherit the CPU features of the current Rust profile.
+    /// Unless a model is set with [`Build::cpu`], the feature is removed from the CPU features of
+    /// the current Rust profile.
+    ///
+    /// # Panics
+    ///
+    /// See [`Cpu::remove`] for the requirements of this method.
     pub fn remove_cpu(&mut self, cpu: impl AsRef<OsStr>) -> &mut Self {
-        match self.cpu.as_mut() {
-            None => {
-                self.cpu = Some(cpu.as_ref().into());
-            }
-            Some(x) => {
-                x.push("-");
-                x.push(cpu);
-            }
-        }
+        let feature = cpu.as_ref().to_string_lossy().into_owned();
+        self.cpu.get_or_insert_with(Cpu::default).remove(feature);
         self
     }
 
@@ -299,8 +556,138 @@ impl Build {
         self
     }
 
+    /// Sets the release mode used when building with the Rust profile `profile`.
+    ///
+    /// Custom profiles, e.g. `[profile.dist]`, are recognized by their name. The `debug` profile
+    /// directory shared by `dev` and `test` is reported as `dev`. Profiles can also be configured
+    /// in the `[package.metadata.zigcli.profiles]` table of the manifest, e.g.
+    /// `dist = { release = "small" }`; the mappings set with this method take precedence.
+    pub fn profile_release(
+        &mut self,
+        profile: impl Into<String>,
+        release: ReleaseMode,
+    ) -> &mut Self {
+        self.profiles.entry(profile.into()).or_default().release = Some(release);
+        self
+    }
+
+    /// Sets the optimization modus used when building with the Rust profile `profile`.
+    ///
+    /// See [`Build::profile_release`] for how profiles are recognized.
+    pub fn profile_optimize(
+        &mut self,
+        profile: impl Into<String>,
+        optimize: Optimize,
+    ) -> &mut Self {
+        self.profiles.entry(profile.into()).or_default().optimize = Some(optimize);
+        self
+    }
+
+    /// Derives the optimization modus from the debug assertions and overflow checks of the current
+    /// Rust profile, in addition to its optimization level.
+    ///
+    /// Optimized profiles without runtime checks are mapped to [`Optimize::ReleaseFast`], instead
+    /// of [`Optimize::ReleaseSafe`].
+    pub fn codegen_optimize(&mut self, enabled: bool) -> &mut Self {
+        self.codegen_optimize = enabled;
+        self
+    }
+
+    /// Passes the codegen setting `option` of the current Rust profile to the build, using its
+    /// conventional name.
+    ///
+    /// The `build.zig` of the package must declare the option.
+    pub fn codegen_option(&mut self, option: CodegenOption) -> &mut Self {
+        self.codegen_option_named(option, option.name())
+    }
+
+    /// Passes the codegen setting `option` of the current Rust profile to the build as the option
+    /// `name`.
+    pub fn codegen_option_named(
+        &mut self,
+        option: CodegenOption,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        self.codegen_options.retain(|(o, _)| *o != option);
+        self.codegen_options.push((option, name.into()));
+        self
+    }
+
+    /// Forwards the enabled Cargo features of the crate to the build as boolean options.
+    ///
+    /// Feature names are normalized to lowercase with underscores, i.e. the `foo-bar` feature is
+    /// forwarded as `-Dfoo_bar=true`. Unless an allow-list is configured with
+    /// [`Build::allow_cargo_feature`], features whose option is not declared by the package are
+    /// skipped, as is the `default` feature. Options set explicitly take precedence.
+    pub fn forward_cargo_features(&mut self) -> &mut Self {
+        self.cargo_features.get_or_insert_with(Default::default);
+        self
+    }
+
+    /// Sets the prefix of the options of forwarded Cargo features, e.g. `feat-` for `-Dfeat-simd`.
+    ///
+    /// Enables [`Build::forward_cargo_features`].
+    pub fn cargo_feature_prefix(&mut self, prefix: impl Into<String>) -> &mut Self {
+        self.cargo_features
+            .get_or_insert_with(Default::default)
+            .prefix = prefix.into();
+        self
+    }
+
+    /// Adds the Cargo feature `feature` to the allow-list of forwarded features.
+    ///
+    /// Allowed features are always forwarded, and passed as `false` when disabled. Enables
+    /// [`Build::forward_cargo_features`].
+    pub fn allow_cargo_feature(&mut self, feature: impl AsRef<str>) -> &mut Self {
+        self.cargo_features
+            .get_or_insert_with(Default::default)
+            .allow
+            .get_or_insert_with(Default::default)
+            .insert(normalize_feature(feature.as_ref()));
+        self
+    }
+
+    /// Forwards the enabled Cargo features as the single list option `name`, i.e.
+    /// `-Dname=foo -Dname=bar`, instead of one boolean option per feature.
+    ///
+    /// The prefix is not applied in this mode. Enables [`Build::forward_cargo_features`].
+    pub fn cargo_features_list(&mut self, name: impl Into<String>) -> &mut Self {
+        self.cargo_features
+            .get_or_insert_with(Default::default)
+            .list = Some(name.into());
+        self
+    }
+
+    /// Writes the Rust build configuration to `cargo_config.zig` in `OUT_DIR`, and passes its
+    /// path to the build as the `-Dcargo-config` option.
+    ///
+    /// The module declares the `CARGO_CFG_*`, enabled `CARGO_FEATURE_*` and `CARGO_PKG_*`
+    /// variables as constants in the `cfg`, `features` and `pkg` namespaces, e.g.
+    /// `cfg.target_os`, `cfg.panic` and `pkg.version`. The `build.zig` of the package can import
+    /// it as a module:
+    ///
+    /// ```zig
+    /// const cargo_config = b.option(std.Build.LazyPath, "cargo-config", "Rust configuration");
+    /// if (cargo_config) |path| {
+    ///     lib.root_module.addAnonymousImport("cargo_config", .{ .root_source_file = path });
+    /// }
+    /// ```
+    pub fn cargo_config(&mut self) -> &mut Self {
+        self.cargo_config_named("cargo-config")
+    }
+
+    /// Writes the Rust build configuration module like [`Build::cargo_config`], passing its path
+    /// as the option `name`.
+    pub fn cargo_config_named(&mut self, name: impl Into<String>) -> &mut Self {
+        self.cargo_config = Some(name.into());
+        self
+    }
+
     /// Adds the option `option` to the build configuration.
     ///
+    /// See [`Build::define_bool`] and the related methods for typed options, which report errors
+    /// instead of panicking.
+    ///
     /// # Panics
     ///
     /// Options must take the form `-Dfoo`.
@@ -347,6 +734,122 @@ impl Build {
         self
     }
 
+    /// Links the library `lib` into the Rust crate after a successful build, e.g. `static=foo`.
+    ///
+    /// The library directory of the prefix is added to the native search path.
+    pub fn link_lib(&mut self, lib: impl Into<String>) -> &mut Self {
+        self.link_libs.push(lib.into());
+        self
+    }
+
+    /// Reruns the build script when `path` changes.
+    ///
+    /// If no path is set, the build script is rerun when the package directory changes.
+    pub fn rerun_if_changed(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.rerun_if_changed
+            .push(env::current_dir().unwrap().join(path));
+        self
+    }
+
+    /// Defines the boolean build option `name`, i.e. `-Dname=true`.
+    ///
+    /// Defining an option replaces its previous definitions. Fails if the name is invalid or
+    /// reserved for a dedicated method, i.e. `target`, `cpu`, `optimize` or `dynamic-linker`.
+    pub fn define_bool(&mut self, name: &str, value: bool) -> Result<&mut Self> {
+        self.define(name, [value.to_string()])
+    }
+
+    /// Defines the integer build option `name`.
+    ///
+    /// See [`Build::define_bool`] for the requirements of this method.
+    pub fn define_int(&mut self, name: &str, value: i64) -> Result<&mut Self> {
+        self.define(name, [value.to_string()])
+    }
+
+    /// Defines the string build option `name`.
+    ///
+    /// See [`Build::define_bool`] for the requirements of this method.
+    pub fn define_str(&mut self, name: &str, value: impl AsRef<str>) -> Result<&mut Self> {
+        self.define(name, [value.as_ref()])
+    }
+
+    /// Defines the enum build option `name` with the tag `value`.
+    ///
+    /// The tag must be a valid zig identifier. See [`Build::define_bool`] for the other
+    /// requirements of this method.
+    pub fn define_enum(&mut self, name: &str, value: impl AsRef<str>) -> Result<&mut Self> {
+        let value = value.as_ref();
+        let mut chars = value.chars();
+        let identifier = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !identifier {
+            return Err(Error::InvalidOption(format!(
+                "`{}` is not a valid tag of the enum option `{}`",
+                value, name
+            )));
+        }
+        self.define(name, [value])
+    }
+
+    /// Defines the list build option `name`, passing one `-Dname=value` entry per value.
+    ///
+    /// An empty list does not define the option, leaving its default value. See
+    /// [`Build::define_bool`] for the other requirements of this method.
+    pub fn define_list(
+        &mut self,
+        name: &str,
+        values: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<&mut Self> {
+        let values = values
+            .into_iter()
+            .map(|value| value.as_ref().to_string())
+            .collect::<Vec<_>>();
+        self.define(name, values)
+    }
+
+    /// Defines the path build option `name`, relative paths are made absolute.
+    ///
+    /// The path is passed as is, even if it is not valid unicode. See [`Build::define_bool`] for
+    /// the other requirements of this method.
+    pub fn define_path(&mut self, name: &str, path: impl AsRef<Path>) -> Result<&mut Self> {
+        let path = env::current_dir().unwrap().join(path);
+        self.define(name, [path])
+    }
+
+    fn define(
+        &mut self,
+        name: &str,
+        values: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    ) -> Result<&mut Self> {
+        if matches!(name, "target" | "cpu" | "optimize" | "dynamic-linker") {
+            return Err(Error::ReservedOption(name.to_string()));
+        }
+        let valid = !name.is_empty()
+            && !name.starts_with('-')
+            && !name.contains(|c: char| c == '=' || c.is_whitespace() || c.is_control());
+        if !valid {
+            return Err(Error::InvalidOption(format!(
+                "`{}` is not a valid name",
+                name
+            )));
+        }
+
+        // Repeated definitions would turn the option into a list.
+        let prefix = format!("-D{}", name);
+        self.options.retain(|option| {
+            let option = option.to_string_lossy();
+            option.split('=').next() != Some(&prefix)
+        });
+        for value in values {
+            let mut option = OsString::from(format!("{}=", prefix));
+            option.push(value);
+            self.options.push(option);
+        }
+        Ok(self)
+    }
+
     /// Sets the lines of reference trace to show per compile error.
     pub fn reference_trace(&mut self, reference_trace: usize) -> &mut Self {
         self.reference_trace = Some(reference_trace);
@@ -439,96 +942,578 @@ impl Build {
         self
     }
 
-    /// Executes `zig build` command, compiling the library with all the configured options.
-    pub fn build(&mut self) -> PathBuf {
-        // Determine the prefix path if not specified.
-        if self.prefix.is_none() {
-            let mut prefix = PathBuf::from(getenv_unwrap("OUT_DIR"));
-            prefix.push("zig-out");
-            self.prefix(prefix);
+    /// Returns the steps declared by the package, as listed by `zig build -l`.
+    ///
+    /// Returns `None` if the steps could not be listed.
+    pub fn list_steps(&mut self) -> Option<Vec<BuildStep>> {
+        let mut cmd = self.introspection_command();
+        cmd.arg("-l");
+        match command_output(&mut cmd) {
+            Some(list) => Some(parse_steps(&list)),
+            None => {
+                eprintln!("Warning: failed to list the steps of the zig package.");
+                None
+            }
         }
+    }
 
-        // Determine the optimization level, if not specified.
-        if self.release.is_none() && self.optimize.is_none() {
-            let default_opt_level = match &getenv_unwrap("PROFILE")[..] {
-                "debug" => Optimize::Debug,
-                "release" | "bench" => Optimize::Default,
-                unknown => {
-                    eprintln!(
-                        "Warning: unknown Rust profile={}; defaulting to a release build.",
-                        unknown
-                    );
-                    Optimize::Default
-                }
-            };
+    /// Returns the options declared by the package, as listed by `zig build -h`.
+    ///
+    /// The options are listed with the target, CPU and options configured so far, as `build.zig`
+    /// may declare some options only for some of them. They are only listed again once the
+    /// target or CPU changes. Returns `None` if the options could not be listed.
+    pub fn project_options(&mut self) -> Option<Vec<ProjectOption>> {
+        self.zig_build_help()
+            .map(|help| parse_project_options(&help))
+    }
 
-            let opt_level = match &getenv_unwrap("OPT_LEVEL")[..] {
-                "0" => Optimize::Debug,
-                "1" | "2" | "3" => Optimize::ReleaseSafe,
-                "s" | "z" => Optimize::ReleaseSmall,
-                unknown => {
-                    eprintln!(
-                        "Warning: unknown opt-level={}; defaulting to a {:?} build.",
-                        unknown, default_opt_level
-                    );
-                    default_opt_level
-                }
-            };
+    /// Sets the amount of steps reported in the build summary, this will default to
+    /// [`SummaryMode::All`] if not specified.
+    pub fn summary(&mut self, mode: SummaryMode) -> &mut Self {
+        self.summary = mode;
+        self
+    }
 
-            if default_opt_level == Optimize::Default {
-                self.release(ReleaseMode::Auto);
-            }
-            self.optimize(opt_level);
-        }
+    /// Writes the build summary as JSON to `zig-build-summary.json` in `OUT_DIR`.
+    pub fn summary_json(&mut self, enabled: bool) -> &mut Self {
+        self.summary_json = enabled;
+        self
+    }
 
-        // Determine the target and CPU features, if not specified.
-        if self.target.is_none() && self.cpu.is_none() {
-            let (target, arch) = translate_target_triple();
-            self.target(target);
+    /// Sets how the output of the build is reported, this will default to
+    /// [`OutputMode::Streamed`] if not specified.
+    ///
+    /// In any case, the output is saved to `zig-build.log` in `OUT_DIR`.
+    pub fn output(&mut self, mode: OutputMode) -> &mut Self {
+        self.output = mode;
+        self
+    }
+
+    /// Sets the number of lines of output included in the error of a failed build, this will
+    /// default to 20 if not specified.
+    pub fn error_output_lines(&mut self, lines: usize) -> &mut Self {
+        self.error_output_lines = lines;
+        self
+    }
+
+    /// Sets a callback receiving the progress reported by zig through `ZIG_PROGRESS`.
+    ///
+    /// The callback is called from another thread, whenever zig reports an update. Progress is
+    /// only reported on Unix platforms, by zig 0.13 or later.
+    pub fn on_progress<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: FnMut(&Progress) + Send + 'static,
+    {
+        self.progress_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Sets the interval between the progress status lines, this will default to 10 seconds if
+    /// not specified.
+    ///
+    /// Status lines are printed like the output, and also reported as Cargo warnings with
+    /// [`OutputMode::Verbose`].
+    pub fn progress_interval(&mut self, interval: Duration) -> &mut Self {
+        self.progress_interval = interval;
+        self
+    }
 
-            let features = std::iter::once("baseline")
-                .chain(getenv_unwrap("CARGO_CFG_TARGET_FEATURE").split(','))
-                .map(|feature| translate_arch_feature(&arch, feature))
-                .collect::<Vec<_>>()
-                .join("+");
-            self.cpu(features);
-        } else if self.target.is_none() {
-            let (target, _) = translate_target_triple();
-            self.target(target);
+    /// Executes `zig build` command, compiling the library with all the configured options.
+    ///
+    /// The configuration can be overridden with the following environment variables, which take
+    /// precedence over the builder settings:
+    ///
+    /// - `ZIGCLI_OPTIMIZE`: an optimization modus, e.g. `ReleaseSafe`, or a release mode, e.g.
+    ///   `fast`.
+    /// - `ZIGCLI_TARGET`: the zig target, e.g. `x86_64-linux-musl`.
+    /// - `ZIGCLI_CPU`: the zig CPU, e.g. `native` or `x86_64_v3+avx512f`.
+    /// - `ZIGCLI_JOBS`: the number of concurrent jobs.
+    /// - `ZIGCLI_OPTIONS`: whitespace separated `-D` options, replacing the options of the same
+    ///   name. Options given several times are list options.
+    /// - `ZIGCLI_DRY_RUN`: if set to anything but `0` or `false`, the command is printed instead of
+    ///   executed, and an empty prefix is created.
+    ///
+    /// Each variable can be scoped to a package and to the Rust target, by appending their names
+    /// with dashes replaced by underscores, e.g. `ZIGCLI_OPTIONS_x86_64_unknown_linux_gnu`. The
+    /// most specific variable is used, in the order `<VAR>_<package>_<target>`,
+    /// `<VAR>_<package>`, `<VAR>_<target>` and `<VAR>`. The package name is the name passed to
+    /// [`Build::from_manifest_package`], or the name of the package directory otherwise.
+    pub fn build(&mut self) -> PathBuf {
+        self.build_artifacts().prefix
+    }
+
+    /// Executes `zig build` command like [`Build::build`], returning the prefix path and the
+    /// summary of the build.
+    pub fn build_artifacts(&mut self) -> Artifacts {
+        match self.try_build() {
+            Ok(artifacts) => artifacts,
+            // The diagnostics have already been reported, ahead of the output of zig.
+            Err(Error::Build { status, .. }) => fail(&format!(
+                "command did not execute successfully, got: {}",
+                status
+            )),
+            Err(e) => fail(&e.to_string()),
         }
+    }
 
-        // Determine the cache dir, if not set.
-        if self.cache_dir.is_none() {
-            let mut cache_dir = PathBuf::from(getenv_unwrap("OUT_DIR"));
-            cache_dir.push(".zig-cache");
-            self.cache_dir(cache_dir);
+    /// Executes `zig build` command like [`Build::build_artifacts`], returning an error if the
+    /// command can not be executed or fails.
+    ///
+    /// The diagnostics reported by zig are parsed into the returned [`Error::Build`]. On failure,
+    /// a deduplicated summary of the diagnostics is printed ahead of the output of zig. Warnings
+    /// are reported to Cargo, so that they are shown even if the build succeeds. Invalid
+    /// configurations and environments are reported as [`Error::Config`].
+    pub fn try_build(&mut self) -> Result<Artifacts> {
+        self.resolve_defaults()?;
+
+        if self.dry_run()? {
+            let cmd = self.zig_command(self.jobs);
+            println!("cargo:warning=dry run of zig build: {:?}", cmd);
+            let prefix = self.prefix.clone().unwrap();
+            if let Err(e) = fs::create_dir_all(&prefix) {
+                let message = format!("failed to create {}: {}", prefix.display(), e);
+                return Err(Error::Io(message));
+            }
+            return Ok(Artifacts {
+                prefix,
+                summary: None,
+            });
         }
 
-        let mut cmd = Command::new(self.zig_executable());
-        cmd.current_dir(&self.path);
-        cmd.arg("build");
+        // The tokens are held until the build ends.
+        let (jobs, jobserver) = match self.jobs {
+            Some(jobs) => (Some(jobs), None),
+            None if self.jobserver => {
+                let (jobs, jobserver) = self.acquire_jobs();
+                (Some(jobs), jobserver)
+            }
+            None => (None, None),
+        };
+        let mut cmd = self.zig_command(jobs);
 
-        // Configure step.
-        if let Some(step) = &self.step {
-            cmd.arg(step.clone());
+        println!("running: {:?}", cmd);
+        let prefix = format!("[zig:{}]", self.package_name());
+        let log = PathBuf::from(self.getenv_unwrap("OUT_DIR")?).join("zig-build.log");
+        let pipe = match progress_pipe() {
+            Ok(pipe) => Some(pipe),
+            Err(e) if e.kind() == ErrorKind::Unsupported => None,
+            Err(e) => {
+                eprintln!("Warning: failed to create the progress pipe: {}", e);
+                None
+            }
+        };
+        let mut callback = self.progress_callback.take();
+        let (mode, interval) = (self.output, self.progress_interval);
+        let result = thread::scope(|scope| {
+            let mut writer = None;
+            if let Some((reader, pipe_writer)) = pipe {
+                inherit_progress_pipe(&mut cmd, &pipe_writer);
+                writer = Some(pipe_writer);
+                let (prefix, callback) = (&prefix, callback.as_mut());
+                scope.spawn(move || report_progress(reader, mode, prefix, interval, callback));
+            }
+            let result = run_command(&mut cmd, mode, &prefix, Some(&log));
+            // Closing the write end ends the progress stream once zig has exited.
+            drop(writer);
+            result
+        });
+        self.progress_callback = callback;
+        drop(jobserver);
+        let output = match result {
+            Ok(output) => output,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {
+                return Err(Error::Command(format!("{}\nis `zig` not installed?", e)));
+            }
+            Err(e) => return Err(Error::Command(e.to_string())),
+        };
+        let status = output.status;
+        let stderr = output.stderr;
+
+        let summary = BuildSummary::parse(&stderr);
+        if let (true, Some(summary)) = (self.summary_json, &summary) {
+            let path = PathBuf::from(self.getenv_unwrap("OUT_DIR")?).join("zig-build-summary.json");
+            if let Err(e) = fs::write(&path, summary.to_json()) {
+                eprintln!("Warning: failed to write {}: {}", path.display(), e);
+            }
         }
 
-        // Configure general options.
-        if let Some(prefix) = &self.prefix {
-            cmd.arg("--prefix");
-            cmd.arg(prefix.clone());
-        }
-        if let Some(prefix_lib_dir) = &self.prefix_lib_dir {
-            cmd.arg("--prefix-lib-dir");
-            cmd.arg(prefix_lib_dir.clone());
+        let diagnostics = parse_diagnostics(&stderr);
+        for warning in diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+        {
+            for line in warning.to_string().lines() {
+                println!("cargo:warning={}", line);
+            }
         }
-        if let Some(prefix_exe_dir) = &self.prefix_exe_dir {
-            cmd.arg("--prefix-exe-dir");
-            cmd.arg(prefix_exe_dir.clone());
+        if !status.success() {
+            let tail = output.lines.len().saturating_sub(self.error_output_lines);
+            let error = Error::Build {
+                status: status.to_string(),
+                diagnostics,
+                output: output.lines[tail..].to_vec(),
+                killed: killed_by_sigkill(&status),
+            };
+            // Unless it was streamed, the output follows the summary of the diagnostics.
+            eprintln!("{}", error);
+            if self.output == OutputMode::Quiet {
+                eprintln!();
+                for line in &output.lines {
+                    eprintln!("{} {}", prefix, line);
+                }
+            }
+            return Err(error);
         }
-        if let Some(prefix_include_dir) = &self.prefix_include_dir {
-            cmd.arg("--prefix-include-dir");
-            cmd.arg(prefix_include_dir.clone());
+        println!("{status}");
+
+        let prefix = match &self.prefix {
+            None => unreachable!(),
+            Some(prefix) => prefix.clone(),
+        };
+        if !self.link_libs.is_empty() {
+            let lib_dir = match &self.prefix_lib_dir {
+                Some(dir) => dir.clone(),
+                None => prefix.join("lib"),
+            };
+            println!("cargo:rustc-link-search=native={}", lib_dir.display());
+            for lib in &self.link_libs {
+                println!("cargo:rustc-link-lib={}", lib);
+            }
+        }
+        Ok(Artifacts { prefix, summary })
+    }
+
+    /// Returns the `zig build` command [`Build::build`] would execute, without executing it.
+    ///
+    /// All the defaults are resolved as for a build, e.g. the target and CPU derived from the
+    /// Rust target, but the limit of concurrent jobs is only sized with the jobserver of Cargo
+    /// when building. Resolving them has the same side effects as a build:
+    /// - the resolved defaults are stored in the configuration,
+    /// - the package may be queried with `zig build -h`, and the LLVM versions of zig and `rustc`
+    ///   compared,
+    /// - the file of [`Build::cargo_config`] is written,
+    /// - the `cargo:rerun-if-*` directives of the build are printed.
+    ///
+    /// Invalid configurations and environments are reported as [`Error::Config`].
+    pub fn command(&mut self) -> Result<Command> {
+        self.resolve_defaults()?;
+        Ok(self.zig_command(self.jobs))
+    }
+
+    /// Returns the arguments of the command returned by [`Build::command`].
+    pub fn args(&mut self) -> Result<Vec<OsString>> {
+        let cmd = self.command()?;
+        Ok(cmd.get_args().map(OsStr::to_os_string).collect())
+    }
+}
+
+impl Build {
+    /// Translates the Rust target triple `target` to a zig target and the default CPU model.
+    ///
+    /// The OS version range and glibc version are derived from the environment.
+    fn translate_target(&mut self, target: &str) -> Result<(Target, Cpu)> {
+        let (mut zig_target, cpu) = self.lookup_target(target)?;
+
+        let deployment_target = match &*zig_target.os {
+            "macos" => Some("MACOSX_DEPLOYMENT_TARGET"),
+            "ios" => Some("IPHONEOS_DEPLOYMENT_TARGET"),
+            "tvos" => Some("TVOS_DEPLOYMENT_TARGET"),
+            "watchos" => Some("WATCHOS_DEPLOYMENT_TARGET"),
+            "visionos" => Some("XROS_DEPLOYMENT_TARGET"),
+            _ => None,
+        };
+        if let Some(var) = deployment_target {
+            self.rerun_if_env_changed(var);
+            if let Some(version) = self.getenv_os(var) {
+                match version.to_string_lossy().parse::<OsVersion>() {
+                    Ok(min) => {
+                        zig_target.os_version_range = Some(OsVersionRange { min, max: None })
+                    }
+                    Err(e) => {
+                        return Err(Error::Config(format!("invalid value of `{}`: {}", var, e)));
+                    }
+                }
+            }
+        }
+
+        let gnu = matches!(&zig_target.abi, Some(abi) if abi.starts_with("gnu"));
+        if gnu && zig_target.os == "linux" {
+            self.rerun_if_env_changed("ZIGCLI_GLIBC_VERSION");
+            if let Some(version) = self.getenv_os("ZIGCLI_GLIBC_VERSION") {
+                match version.to_string_lossy().parse() {
+                    Ok(version) => zig_target.glibc_version = Some(version),
+                    Err(e) => {
+                        let message = format!("invalid value of `ZIGCLI_GLIBC_VERSION`: {}", e);
+                        return Err(Error::Config(message));
+                    }
+                }
+            }
+        }
+
+        Ok((zig_target, cpu))
+    }
+
+    fn lookup_target(&mut self, target: &str) -> Result<(Target, Cpu)> {
+        if let Some(zig_target) = self.target_mappings.get(target) {
+            let cpu = parse_cpu(translate_target_cpu_model(target))?;
+            return Ok((zig_target.clone(), cpu));
+        }
+
+        let spec = match self.target_spec.clone() {
+            Some(path) => Some(path),
+            None if translate_target_triple(target).is_some() => None,
+            None => {
+                self.rerun_if_env_changed("RUST_TARGET_PATH");
+                let search_path = self.getenv_os("RUST_TARGET_PATH");
+                TargetSpec::find(target, search_path.as_deref())
+            }
+        };
+        if let Some(path) = spec {
+            self.print_rerun_directive(format!("cargo:rerun-if-changed={}", path.display()));
+            let translated = TargetSpec::load(&path).and_then(|spec| spec.translate());
+            return match translated {
+                Ok((zig_target, cpu)) => Ok((parse_target(&zig_target)?, cpu)),
+                Err(e) => Err(Error::Config(format!(
+                    "failed to translate target specification {}: {}",
+                    path.display(),
+                    e
+                ))),
+            };
+        }
+
+        let cpu = parse_cpu(translate_target_cpu_model(target))?;
+        if let Some(zig_target) = translate_target_triple(target) {
+            return Ok((parse_target(zig_target)?, cpu));
+        }
+        match guess_target_triple(target) {
+            Some(zig_target) => {
+                eprintln!(
+                    "Warning: unknown Rust target={}; guessing zig target={}.",
+                    target, zig_target
+                );
+                Ok((parse_target(&zig_target)?, cpu))
+            }
+            None => Err(Error::Config(format!("target not supported {target:?}"))),
+        }
+    }
+
+    /// Applies the `ZIGCLI_*` environment variable overrides documented in [`Build::build`],
+    /// except for `ZIGCLI_CPU`, which is applied once the CPU has been derived.
+    fn apply_env_overrides(&mut self) -> Result<()> {
+        if let Some(optimize) = self.getenv_override("ZIGCLI_OPTIMIZE")? {
+            let optimize = optimize.to_string_lossy();
+            if let Some(optimize) = Optimize::from_name(&optimize) {
+                self.release = None;
+                self.optimize = Some(optimize);
+            } else if let Some(release) = ReleaseMode::from_name(&optimize) {
+                self.release = Some(release);
+                self.optimize = None;
+            } else {
+                let message = format!("invalid value of `ZIGCLI_OPTIMIZE`: {}", optimize);
+                return Err(Error::Config(message));
+            }
+        }
+        if let Some(target) = self.getenv_override("ZIGCLI_TARGET")? {
+            self.target = Some(target);
+        }
+        if let Some(jobs) = self.getenv_override("ZIGCLI_JOBS")? {
+            match jobs.to_string_lossy().parse() {
+                Ok(jobs) => self.jobs = Some(jobs),
+                Err(e) => {
+                    let message = format!("invalid value of `ZIGCLI_JOBS`: {}", e);
+                    return Err(Error::Config(message));
+                }
+            }
+        }
+        if let Some(options) = self.getenv_override("ZIGCLI_OPTIONS")? {
+            // Options given several times are list options, and replace the options of the same
+            // name as a whole.
+            let mut definitions = Vec::<(&str, Vec<&str>)>::new();
+            let options = options.to_string_lossy();
+            for option in options.split_whitespace() {
+                let Some(definition) = option.strip_prefix("-D") else {
+                    return Err(Error::Config(format!(
+                        "invalid value of `ZIGCLI_OPTIONS`: `{}` is not a `-D` option",
+                        option
+                    )));
+                };
+                let (name, value) = definition.split_once('=').unwrap_or((definition, "true"));
+                match definitions.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, values)) => values.push(value),
+                    None => definitions.push((name, vec![value])),
+                }
+            }
+            for (name, values) in definitions {
+                if let Err(e) = self.define_list(name, values) {
+                    let message = format!("invalid value of `ZIGCLI_OPTIONS`: {}", e);
+                    return Err(Error::Config(message));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves the defaults of the configuration from the environment, e.g. the prefix, the
+    /// optimization mode and the target.
+    fn resolve_defaults(&mut self) -> Result<()> {
+        self.apply_env_overrides()?;
+        self.rerun_if_package_changed();
+
+        // Determine the prefix path if not specified.
+        if self.prefix.is_none() {
+            let mut prefix = PathBuf::from(self.getenv_unwrap("OUT_DIR")?);
+            prefix.push("zig-out");
+            self.prefix(prefix);
+        }
+
+        // Determine the optimization level, if not specified.
+        let profile_mode = if self.release.is_none() && self.optimize.is_none() {
+            let profile = self.rust_profile()?;
+            self.profile_mode(&profile)?
+        } else {
+            None
+        };
+        if let Some(mode) = profile_mode {
+            self.release = mode.release;
+            self.optimize = mode.optimize;
+        } else if self.release.is_none() && self.optimize.is_none() {
+            let default_opt_level = match &self.getenv_unwrap("PROFILE")?[..] {
+                "debug" => Optimize::Debug,
+                "release" | "bench" => Optimize::Default,
+                unknown => {
+                    eprintln!(
+                        "Warning: unknown Rust profile={}; defaulting to a release build.",
+                        unknown
+                    );
+                    Optimize::Default
+                }
+            };
+
+            let checked = !self.codegen_optimize || {
+                let codegen = self.rust_codegen();
+                codegen.debug_assertions || codegen.overflow_checks
+            };
+            let opt_level = match &self.getenv_unwrap("OPT_LEVEL")?[..] {
+                "0" => Optimize::Debug,
+                "1" | "2" | "3" if checked => Optimize::ReleaseSafe,
+                "1" | "2" | "3" => Optimize::ReleaseFast,
+                "s" | "z" => Optimize::ReleaseSmall,
+                unknown => {
+                    eprintln!(
+                        "Warning: unknown opt-level={}; defaulting to a {:?} build.",
+                        unknown, default_opt_level
+                    );
+                    default_opt_level
+                }
+            };
+
+            if default_opt_level == Optimize::Default {
+                self.release(ReleaseMode::Auto);
+            }
+            self.optimize(opt_level);
+        }
+
+        // Determine the target and CPU features, if not specified.
+        if self.target.is_none() {
+            let rust_target = self.getenv_unwrap("TARGET")?;
+            let (target, target_cpu) = self.translate_target(&rust_target)?;
+            let arch = target.arch.clone();
+            self.target = Some(target.to_string().into());
+
+            // An explicit model replaces the derived CPU, whereas explicit features are applied
+            // on top of it.
+            if self.cpu.as_ref().map_or(true, |cpu| cpu.model().is_none()) {
+                let mut cpu = self.derive_cpu(&arch, target_cpu)?;
+                if let Some(explicit) = &self.cpu {
+                    cpu.merge(explicit);
+                }
+                self.cpu = Some(cpu);
+            }
+        }
+        // Unlike the features set with the builder, the override always replaces the CPU.
+        if let Some(cpu) = self.getenv_override("ZIGCLI_CPU")? {
+            self.cpu = Some(parse_cpu(&cpu.to_string_lossy())?);
+        }
+
+        // Determine the cache dir, if not set.
+        if self.cache_dir.is_none() {
+            let mut cache_dir = PathBuf::from(self.getenv_unwrap("OUT_DIR")?);
+            cache_dir.push(".zig-cache");
+            self.cache_dir(cache_dir);
+        }
+
+        self.forward_features()?;
+        if let Some(name) = self.cargo_config.clone() {
+            let path = PathBuf::from(self.getenv_unwrap("OUT_DIR")?).join("cargo_config.zig");
+            let vars = self.cargo_config_vars();
+            if let Err(e) = fs::write(&path, cargo_config::generate(vars)) {
+                return Err(Error::Io(format!(
+                    "failed to write {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+            self.define_path(&name, path)?;
+        }
+        self.validate_options()
+    }
+
+    /// Derives the CPU of the current Rust profile for the architecture `arch`, from the default
+    /// CPU `target_cpu` of the Rust target.
+    ///
+    /// The model is replaced with the one requested with `-C target-cpu`, keeping the ABI features
+    /// implied by the triple, e.g. `soft_float`, and the enabled features are applied on top of it.
+    /// Features explicitly disabled with `-C target-feature` are removed, as the model may enable
+    /// them implicitly.
+    fn derive_cpu(&mut self, arch: &str, target_cpu: Cpu) -> Result<Cpu> {
+        let mut cpu = target_cpu;
+        if let Some(model) = self.rustc_codegen_option("target-cpu") {
+            cpu.set_model(translate_arch_cpu(arch, &model));
+        }
+        for feature in self.getenv_unwrap("CARGO_CFG_TARGET_FEATURE")?.split(',') {
+            if let Some(feature) = translate_arch_feature(arch, feature) {
+                cpu.add(feature);
+            }
+        }
+        for features in self.rustc_codegen_options("target-feature") {
+            for feature in features.split(',') {
+                let feature = feature.strip_prefix('-');
+                if let Some(feature) = feature.and_then(|f| translate_arch_feature(arch, f)) {
+                    cpu.remove(feature);
+                }
+            }
+        }
+        Ok(cpu)
+    }
+
+    /// Creates the `zig build` command of the resolved configuration, with the limit of
+    /// concurrent `jobs`.
+    fn zig_command(&mut self, jobs: Option<usize>) -> Command {
+        let derived_options = self.derived_options();
+
+        let mut cmd = Command::new(self.zig_executable());
+        cmd.current_dir(&self.path);
+        cmd.arg("build");
+
+        // Configure steps.
+        cmd.args(&self.steps);
+
+        // Configure general options.
+        if let Some(prefix) = &self.prefix {
+            cmd.arg("--prefix");
+            cmd.arg(prefix.clone());
+        }
+        if let Some(prefix_lib_dir) = &self.prefix_lib_dir {
+            cmd.arg("--prefix-lib-dir");
+            cmd.arg(prefix_lib_dir.clone());
+        }
+        if let Some(prefix_exe_dir) = &self.prefix_exe_dir {
+            cmd.arg("--prefix-exe-dir");
+            cmd.arg(prefix_exe_dir.clone());
+        }
+        if let Some(prefix_include_dir) = &self.prefix_include_dir {
+            cmd.arg("--prefix-include-dir");
+            cmd.arg(prefix_include_dir.clone());
         }
         if let Some(release) = self.release {
             if release == ReleaseMode::Auto {
@@ -578,11 +1563,17 @@ impl Build {
         if self.prominent_compile_errors {
             cmd.arg("--prominent-compile-errors");
         }
-        if let Some(jobs) = self.jobs {
+        if let Some(jobs) = jobs {
             let arg = format!("-j{}", jobs);
             cmd.arg(arg);
         }
-        if let Some(maxrss) = self.maxrss {
+        // Zig defaults to the total memory, ignoring the limits of containers, where the
+        // build would then be killed instead of scheduled within the limit.
+        let maxrss = match self.maxrss {
+            None if self.auto_maxrss => memory_limit().and_then(|l| usize::try_from(l).ok()),
+            maxrss => maxrss,
+        };
+        if let Some(maxrss) = maxrss {
             cmd.arg("--maxrss");
             cmd.arg(maxrss.to_string());
         }
@@ -600,11 +1591,12 @@ impl Build {
 
         // Configure project-specific options.
         if let Some(target) = &self.target {
-            let arg = format!("-Dtarget={}", target.to_string_lossy());
+            let mut arg = OsString::from("-Dtarget=");
+            arg.push(target);
             cmd.arg(arg);
         }
         if let Some(cpu) = &self.cpu {
-            let arg = format!("-Dcpu={}", cpu.to_string_lossy());
+            let arg = format!("-Dcpu={}", cpu);
             cmd.arg(arg);
         }
         if let Some(dynamic_linker) = &self.dynamic_linker {
@@ -626,6 +1618,9 @@ impl Build {
                 cmd.arg(arg);
             }
         }
+        for (name, value) in &derived_options {
+            cmd.arg(format!("-D{}={}", name, value));
+        }
         cmd.args(&self.options);
 
         // Configure advanced options.
@@ -684,37 +1679,222 @@ impl Build {
             cmd.arg("--verbose-llvm-cpu-features");
         }
 
-        println!("running: {:?}", cmd);
-        let status = match cmd.status() {
-            Ok(status) => status,
-            Err(ref e) if e.kind() == ErrorKind::NotFound => {
-                fail(&format!(
-                    "failed to execute command: {}\nis `zig` not installed?",
-                    e
-                ));
+        cmd.arg("--summary").arg(self.summary.name());
+        // The output is piped, so zig only uses colors if Cargo asks for them.
+        match self.getenv_os("CARGO_TERM_COLOR").as_deref() {
+            Some(color) if color == "always" => {
+                cmd.arg("--color").arg("on");
+            }
+            Some(color) if color == "never" => {
+                cmd.arg("--color").arg("off");
             }
-            Err(e) => fail(&format!("failed to execute command: {}", e)),
+            _ => {}
+        }
+
+        // Configure the arguments of run steps.
+        if !self.run_args.is_empty() {
+            cmd.arg("--");
+            cmd.args(&self.run_args);
+        }
+        cmd
+    }
+
+    /// Checks whether `ZIGCLI_DRY_RUN` is set.
+    fn dry_run(&mut self) -> Result<bool> {
+        let dry_run = self.getenv_override("ZIGCLI_DRY_RUN")?;
+        Ok(dry_run.is_some_and(|value| !matches!(value.to_str(), Some("" | "0" | "false"))))
+    }
+
+    /// Acquires the tokens of the jobserver of Cargo, returning the resulting limit of concurrent
+    /// jobs, bounded by `NUM_JOBS`, and the jobserver holding the tokens.
+    fn acquire_jobs(&mut self) -> (usize, Option<Jobserver>) {
+        let max = self
+            .getenv_os("NUM_JOBS")
+            .and_then(|jobs| jobs.to_str()?.parse().ok())
+            .or_else(|| thread::available_parallelism().ok().map(usize::from))
+            .unwrap_or(1)
+            .max(1);
+        let Some(auth) = self
+            .getenv_os("CARGO_MAKEFLAGS")
+            .and_then(|flags| parse_makeflags(&flags.to_string_lossy()))
+        else {
+            return (max, None);
         };
-        if !status.success() {
-            fail(&format!(
-                "command did not execute successfully, got: {}",
-                status
-            ));
+        let mut jobserver = match Jobserver::connect(&auth) {
+            Ok(jobserver) => jobserver,
+            // Inherited descriptors can not be used on this platform without disturbing Cargo.
+            Err(e) if e.kind() == ErrorKind::Unsupported => return (max, None),
+            Err(e) => {
+                eprintln!("Warning: failed to connect to the jobserver: {}", e);
+                return (max, None);
+            }
+        };
+        // The build script holds an implicit token. Waiting briefly for another one keeps zig
+        // from running a single job when all tokens are in use at startup.
+        match jobserver.acquire(max - 1, Duration::from_millis(500)) {
+            Ok(tokens) => (tokens + 1, Some(jobserver)),
+            Err(e) => {
+                eprintln!("Warning: failed to acquire jobserver tokens: {}", e);
+                (jobserver.tokens() + 1, Some(jobserver))
+            }
         }
-        println!("{status}");
+    }
 
-        match &self.prefix {
-            None => unreachable!(),
-            Some(prefix) => prefix.clone(),
+    /// Returns the name passed to [`Build::from_manifest_package`], or the name of the package
+    /// directory otherwise.
+    fn package_name(&self) -> String {
+        match &self.package_name {
+            Some(name) => name.clone(),
+            None => self
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
         }
     }
-}
 
-impl Build {
+    /// Returns the value of the most specific variant of the override variable `var`.
+    ///
+    /// All variants are tracked with `rerun-if-env-changed`.
+    fn getenv_override(&mut self, var: &str) -> Result<Option<OsString>> {
+        let target = self.getenv_unwrap("TARGET")?.replace('-', "_");
+        let package = self.package_name().replace('-', "_");
+
+        let mut candidates = vec![];
+        if !package.is_empty() {
+            candidates.push(format!("{}_{}_{}", var, package, target));
+            candidates.push(format!("{}_{}", var, package));
+        }
+        candidates.push(format!("{}_{}", var, target));
+        candidates.push(var.to_string());
+
+        for candidate in &candidates {
+            self.rerun_if_env_changed(candidate);
+        }
+        Ok(candidates
+            .iter()
+            .find_map(|candidate| self.getenv_os(candidate)))
+    }
+
+    /// Reruns the build script when the environment variable `var` changes.
+    ///
+    /// Once a build script requests any rerun, Cargo no longer reruns it when the files of the
+    /// crate change, so the files of the package are tracked as well.
+    fn rerun_if_env_changed(&mut self, var: &str) {
+        self.rerun_if_package_changed();
+        self.print_rerun_directive(format!("cargo:rerun-if-env-changed={}", var));
+    }
+
+    /// Reruns the build script when the paths of [`Build::rerun_if_changed`] change, or the
+    /// package directory if there are none.
+    fn rerun_if_package_changed(&mut self) {
+        let paths = if self.rerun_if_changed.is_empty() {
+            vec![self.path.clone()]
+        } else {
+            self.rerun_if_changed.clone()
+        };
+        for path in paths {
+            self.print_rerun_directive(format!("cargo:rerun-if-changed={}", path.display()));
+        }
+    }
+
+    /// Prints the rerun `directive`, unless it has already been printed.
+    fn print_rerun_directive(&mut self, directive: String) {
+        if !self.rerun_directives.contains(&directive) {
+            println!("{}", directive);
+            self.rerun_directives.push(directive);
+        }
+    }
+
+    /// Returns the name of the current Rust profile.
+    ///
+    /// `PROFILE` collapses custom profiles to `debug` or `release`, but their names are retained
+    /// by the profile directory in `OUT_DIR`, i.e. `target/<profile>/build/<package>/out`.
+    fn rust_profile(&mut self) -> Result<String> {
+        let out_dir = PathBuf::from(self.getenv_unwrap("OUT_DIR")?);
+        let mut ancestors = out_dir.ancestors().skip(2).map(Path::file_name);
+        let profile = match (ancestors.next(), ancestors.next()) {
+            (Some(Some(build)), Some(Some(profile))) if build == "build" => profile.to_str(),
+            _ => None,
+        };
+        let profile = match profile {
+            Some("debug") => "dev".to_string(),
+            Some(profile) => profile.to_string(),
+            None if self.getenv_unwrap("DEBUG")? == "true" => "dev".to_string(),
+            None => "release".to_string(),
+        };
+        Ok(profile)
+    }
+
+    /// Returns the release mode and optimization modus configured for the Rust profile `profile`.
+    ///
+    /// The manifest is read at most once, and not at all if the configuration was created from it.
+    fn profile_mode(&mut self, profile: &str) -> Result<Option<ProfileMode>> {
+        if let Some(mode) = self.profiles.get(profile) {
+            return Ok(Some(*mode));
+        }
+        let metadata = self
+            .manifest_metadata
+            .get_or_insert_with(manifest::read_metadata);
+        let Some(metadata) = metadata else {
+            return Ok(None);
+        };
+        manifest::profile_mode(metadata, profile).map_err(|e| {
+            Error::Config(format!(
+                "invalid zigcli profile `{}` in manifest: {}",
+                profile, e
+            ))
+        })
+    }
+
     fn zig_executable(&mut self) -> OsString {
         self.getenv_os("ZIG").unwrap_or("zig".into())
     }
 
+    fn getenv_unwrap(&mut self, v: &str) -> Result<String> {
+        match self.getenv_os(v) {
+            Some(s) => Ok(s.to_string_lossy().into_owned()),
+            None => Err(Error::Config(format!(
+                "environment variable `{}` not defined",
+                v
+            ))),
+        }
+    }
+
+    /// Returns the environment variables whose names start with `prefix`, sorted by name.
+    ///
+    /// The variables are tracked with `rerun-if-env-changed`.
+    fn getenv_prefixed(&mut self, prefix: &str) -> Vec<(String, OsString)> {
+        if !self.env_listed {
+            for (var, value) in env::vars_os() {
+                if let Ok(var) = var.into_string() {
+                    self.env_cache.entry(var).or_insert(Some(value));
+                }
+            }
+            self.env_listed = true;
+        }
+        let mut vars = self
+            .env_cache
+            .iter()
+            .filter(|(var, _)| var.starts_with(prefix))
+            .filter_map(|(var, value)| Some((var.clone(), value.clone()?)))
+            .collect::<Vec<_>>();
+        vars.sort();
+        for (var, _) in &vars {
+            self.rerun_if_env_changed(var);
+        }
+        vars
+    }
+
+    /// Returns the variables declared by `cargo_config.zig`, see [`Build::cargo_config`].
+    fn cargo_config_vars(&mut self) -> Vec<(String, String)> {
+        ["CARGO_CFG_", "CARGO_FEATURE_", "CARGO_PKG_"]
+            .iter()
+            .flat_map(|prefix| self.getenv_prefixed(prefix))
+            .filter_map(|(var, value)| Some((var, value.into_string().ok()?)))
+            .collect()
+    }
+
     fn getenv_os(&mut self, v: &str) -> Option<OsString> {
         if let Some(val) = self.env_cache.get(v) {
             return val.clone();
@@ -724,6 +1904,398 @@ impl Build {
         self.env_cache.insert(v.to_string(), r.clone());
         r
     }
+
+    /// Reads the codegen settings of the current Rust profile.
+    fn rust_codegen(&mut self) -> RustCodegen {
+        let debuginfo = match self.rustc_codegen_option("debuginfo") {
+            Some(level) => !matches!(&*level, "0" | "none"),
+            None => self
+                .getenv_os("DEBUG")
+                .is_some_and(|debug| debug != "false" && debug != "0" && debug != "none"),
+        };
+        RustCodegen {
+            debuginfo,
+            debug_assertions: self.getenv_os("CARGO_CFG_DEBUG_ASSERTIONS").is_some(),
+            overflow_checks: self.getenv_os("CARGO_CFG_OVERFLOW_CHECKS").is_some(),
+            panic_unwind: self
+                .getenv_os("CARGO_CFG_PANIC")
+                .map_or(true, |panic| panic == "unwind"),
+            strip: self.rustc_codegen_option("strip"),
+            relocation_model: self.rustc_codegen_option("relocation-model"),
+            code_model: self.rustc_codegen_option("code-model"),
+            force_frame_pointers: self.rustc_codegen_option("force-frame-pointers"),
+            freestanding: self
+                .getenv_os("CARGO_CFG_TARGET_OS")
+                .is_some_and(|os| os == "none"),
+        }
+    }
+
+    /// Returns the last value of the codegen option `name` passed to `rustc`.
+    fn rustc_codegen_option(&mut self, name: &str) -> Option<String> {
+        self.rustc_codegen_options(name).pop()
+    }
+
+    /// Returns all values of the codegen option `name` passed to `rustc`, in order.
+    fn rustc_codegen_options(&mut self, name: &str) -> Vec<String> {
+        self.rustc_options(&["-C", "--codegen"], name)
+    }
+
+    /// Returns all values of the unstable option `name` passed to `rustc`, in order.
+    fn rustc_unstable_options(&mut self, name: &str) -> Vec<String> {
+        self.rustc_options(&["-Z"], name)
+    }
+
+    /// Returns all values of the option `name` passed to `rustc` with one of the flags `flags`.
+    fn rustc_options(&mut self, flags: &[&str], name: &str) -> Vec<String> {
+        let rustflags = match self.getenv_os("CARGO_ENCODED_RUSTFLAGS") {
+            Some(flags) => flags.to_string_lossy().into_owned(),
+            None => return vec![],
+        };
+
+        let mut values = vec![];
+        let mut rustflags = rustflags.split('\x1f').filter(|flag| !flag.is_empty());
+        while let Some(flag) = rustflags.next() {
+            let option = if flags.contains(&flag) {
+                match rustflags.next() {
+                    Some(option) => option,
+                    None => break,
+                }
+            } else {
+                // Short flags are joined with their option, e.g. `-Ctarget-cpu=native`, whereas
+                // long flags use an equal sign, e.g. `--codegen=target-cpu=native`.
+                let joined = flags.iter().find_map(|f| {
+                    if f.starts_with("--") {
+                        flag.strip_prefix(f)?.strip_prefix('=')
+                    } else {
+                        flag.strip_prefix(f)
+                    }
+                });
+                match joined {
+                    Some(option) => option,
+                    None => continue,
+                }
+            };
+            // Boolean options may be passed without a value, e.g. `-C force-frame-pointers`.
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            if key == name {
+                values.push(value.to_string());
+            }
+        }
+        values
+    }
+
+    /// Returns the sanitizers enabled for the current Rust profile.
+    fn rust_sanitizers(&mut self) -> Vec<String> {
+        let mut sanitizers = vec![];
+        let cfg = self.getenv_os("CARGO_CFG_SANITIZE").unwrap_or_default();
+        let flags = self.rustc_unstable_options("sanitizer");
+        for sanitizer in flags.iter().map(|f| &**f).chain([&*cfg.to_string_lossy()]) {
+            for sanitizer in sanitizer.split(',') {
+                if !sanitizer.is_empty() && !sanitizers.iter().any(|s| s == sanitizer) {
+                    sanitizers.push(sanitizer.to_string());
+                }
+            }
+        }
+        sanitizers
+    }
+
+    /// Returns the `-D` options derived from the current Rust profile.
+    ///
+    /// Options set explicitly take precedence, and are never derived.
+    fn derived_options(&mut self) -> Vec<(String, String)> {
+        let mut options = vec![];
+        if !self.codegen_options.is_empty() {
+            let codegen = self.rust_codegen();
+            for (option, name) in &self.codegen_options {
+                if self.has_option(name) {
+                    continue;
+                }
+                if let Some(value) = codegen.value(*option) {
+                    options.push((name.clone(), value));
+                }
+            }
+        }
+
+        // Instrument the zig code with the sanitizers enabled for the Rust code, if the package
+        // supports them. Undeclared options would make the build fail.
+        let sanitizers = self.rust_sanitizers();
+        if !sanitizers.is_empty() {
+            let mut sanitizer_options = sanitizers
+                .iter()
+                .map(|sanitizer| (format!("sanitize-{}", sanitizer), "true".to_string()))
+                .collect::<Vec<_>>();
+            // The C sanitization mode of zig, of the same type as `CodegenOption::SanitizeC`.
+            let sanitize_c = CodegenOption::SanitizeC.name().to_string();
+            sanitizer_options.push((sanitize_c, "full".to_string()));
+
+            for (name, value) in sanitizer_options {
+                if self.has_option(&name) {
+                    continue;
+                }
+                if self.declares_option(&name) {
+                    options.retain(|(n, _)| *n != name);
+                    options.push((name, value));
+                } else {
+                    eprintln!(
+                        "Warning: undeclared zig option -D{}; skipping sanitizer instrumentation.",
+                        name
+                    );
+                }
+            }
+        }
+
+        // Emit LLVM bitcode, so that `rustc` can optimize across the language boundary.
+        if self.rust_linker_plugin_lto() && !self.has_option("emit-llvm-bc") {
+            self.check_llvm_versions();
+            if self.declares_option("emit-llvm-bc") {
+                options.push(("emit-llvm-bc".to_string(), "true".to_string()));
+            } else {
+                eprintln!(
+                    "Warning: undeclared zig option -Demit-llvm-bc; skipping cross-language LTO."
+                );
+            }
+        }
+
+        options
+    }
+
+    /// Defines the options of the Cargo features, as configured with
+    /// [`Build::forward_cargo_features`].
+    fn forward_features(&mut self) -> Result<()> {
+        let Some(config) = self.cargo_features.clone() else {
+            return Ok(());
+        };
+        let enabled = self
+            .getenv_prefixed("CARGO_FEATURE_")
+            .into_iter()
+            .map(|(var, _)| normalize_feature(&var["CARGO_FEATURE_".len()..]))
+            .filter(|feature| feature != "default")
+            .filter(|feature| config.allow.as_ref().map_or(true, |a| a.contains(feature)))
+            .collect::<BTreeSet<_>>();
+
+        let result = if let Some(name) = &config.list {
+            if self.has_option(name) {
+                return Ok(());
+            }
+            self.define_list(name, &enabled).map(|_| ())
+        } else {
+            // Without an allow-list, only the features declared as options are forwarded.
+            let (features, declared) = match &config.allow {
+                Some(allow) => (allow.clone(), None),
+                None => (enabled.clone(), self.project_options()),
+            };
+            features.iter().try_for_each(|feature| {
+                let name = format!("{}{}", config.prefix, feature);
+                let undeclared = declared
+                    .as_ref()
+                    .is_some_and(|declared| !declared.iter().any(|o| o.name == name));
+                if self.has_option(&name) || undeclared {
+                    return Ok(());
+                }
+                self.define_bool(&name, enabled.contains(feature))
+                    .map(|_| ())
+            })
+        };
+        result.map_err(|e| Error::Config(format!("failed to forward the Cargo features: {}", e)))
+    }
+
+    /// Checks whether the option `name` has been set explicitly.
+    fn has_option(&self, name: &str) -> bool {
+        let prefix = format!("-D{}", name);
+        self.options.iter().any(|option| {
+            let option = option.to_string_lossy();
+            option.split('=').next() == Some(&prefix)
+        })
+    }
+
+    /// Checks whether the package declares the option `name`.
+    ///
+    /// Assumes that the option is declared if the options can not be listed.
+    fn declares_option(&mut self, name: &str) -> bool {
+        match self.project_options() {
+            Some(options) => options.iter().any(|option| option.name == name),
+            None => true,
+        }
+    }
+
+    /// Checks the names and values of the configured options against the options declared by
+    /// the package, suggesting the closest declared option for unknown names.
+    fn validate_options(&mut self) -> Result<()> {
+        if self.options.is_empty() {
+            return Ok(());
+        }
+        let Some(declared) = self.project_options() else {
+            return Ok(());
+        };
+        let mut errors = vec![];
+        for option in &self.options {
+            let option = option.to_string_lossy();
+            let option = option.strip_prefix("-D").unwrap_or(&option);
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (option, None),
+            };
+            match declared.iter().find(|o| o.name == name) {
+                Some(o) if o.accepts(value) => {}
+                Some(o) => {
+                    let mut error = format!(
+                        "invalid value {:?} for option -D{} of type {}",
+                        value.unwrap_or_default(),
+                        name,
+                        o.kind
+                    );
+                    if !o.values.is_empty() {
+                        error.push_str(&format!("; supported values: {}", o.values.join(", ")));
+                    }
+                    errors.push(error);
+                }
+                None => match closest_option(name, &declared) {
+                    Some(closest) => errors.push(format!(
+                        "unknown option -D{}; did you mean -D{}?",
+                        name, closest
+                    )),
+                    None => errors.push(format!("unknown option -D{}", name)),
+                },
+            }
+        }
+        if !errors.is_empty() {
+            return Err(Error::InvalidOption(format!(
+                "the zig package at {} does not accept the configured options:\n{}",
+                self.path.display(),
+                errors.join("\n")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks whether `rustc` performs linker-plugin-based LTO.
+    fn rust_linker_plugin_lto(&mut self) -> bool {
+        match self.rustc_codegen_option("linker-plugin-lto") {
+            // The option may also specify the path to the linker plugin.
+            Some(value) => !matches!(&*value, "n" | "no" | "off" | "false"),
+            None => false,
+        }
+    }
+
+    /// Warns if the LLVM major versions of zig and `rustc` differ, as the bitcode emitted by one
+    /// of them may then be rejected or silently ignored by the linker plugin of the other.
+    ///
+    /// The versions are only compared once per build.
+    fn check_llvm_versions(&mut self) {
+        if self.llvm_versions_checked {
+            return;
+        }
+        self.llvm_versions_checked = true;
+        let rustc = self.getenv_os("RUSTC").unwrap_or("rustc".into());
+        let mut rustc = Command::new(rustc);
+        rustc.arg("-vV");
+        let rustc_version = command_output(&mut rustc).and_then(|output| {
+            let version = output
+                .lines()
+                .find_map(|l| l.strip_prefix("LLVM version: "))?;
+            llvm_major_version(version)
+        });
+
+        // Zig does not report its LLVM version directly, but the one of its bundled clang matches.
+        let mut zig = Command::new(self.zig_executable());
+        zig.arg("cc").arg("--version");
+        let zig_version = command_output(&mut zig).and_then(|output| {
+            let version = output.split("clang version ").nth(1)?;
+            llvm_major_version(version)
+        });
+
+        match (rustc_version, zig_version) {
+            (Some(rustc_version), Some(zig_version)) if rustc_version != zig_version => {
+                eprintln!(
+                    "Warning: mismatched LLVM versions zig={} rustc={}; LTO will not work.",
+                    zig_version, rustc_version
+                );
+            }
+            (Some(_), Some(_)) => {}
+            _ => eprintln!("Warning: failed to compare the LLVM versions of zig and rustc."),
+        }
+    }
+
+    /// Returns the target and CPU options the package is queried with, once they are resolved.
+    fn introspection_context(&self) -> Vec<OsString> {
+        let mut context = vec![];
+        if let Some(target) = &self.target {
+            let mut arg = OsString::from("-Dtarget=");
+            arg.push(target);
+            context.push(arg);
+        }
+        if let Some(cpu) = &self.cpu {
+            context.push(format!("-Dcpu={}", cpu).into());
+        }
+        context
+    }
+
+    /// Returns a `zig build` command for querying the package, sharing the caches of the build.
+    ///
+    /// The configured target, CPU and options are passed, as they may change the steps and
+    /// options declared by the package.
+    fn introspection_command(&mut self) -> Command {
+        let mut cmd = Command::new(self.zig_executable());
+        cmd.current_dir(&self.path);
+        cmd.arg("build");
+        cmd.args(self.introspection_context());
+        cmd.args(&self.options);
+        if let Some(build_file) = &self.build_file {
+            cmd.arg("--build-file").arg(build_file);
+        }
+        // Keep the cache out of the package directory, even before the build sets it.
+        let cache_dir = self.cache_dir.clone().or_else(|| {
+            self.getenv_os("OUT_DIR")
+                .map(|dir| PathBuf::from(dir).join(".zig-cache"))
+        });
+        if let Some(cache_dir) = &cache_dir {
+            cmd.arg("--cache-dir").arg(cache_dir);
+        }
+        if let Some(global_cache_dir) = &self.global_cache_dir {
+            cmd.arg("--global-cache-dir").arg(global_cache_dir);
+        }
+        if let Some(zig_lib_dir) = &self.zig_lib_dir {
+            cmd.arg("--zig-lib-dir").arg(zig_lib_dir);
+        }
+        cmd
+    }
+
+    /// Returns the output of `zig build -h`, listing the steps and options of the package.
+    ///
+    /// The output, or the failure to get it, is cached until the target or CPU changes.
+    fn zig_build_help(&mut self) -> Option<String> {
+        let context = self.introspection_context();
+        if let Some((cached, help)) = &self.build_help {
+            if *cached == context {
+                return help.clone();
+            }
+        }
+
+        let mut cmd = self.introspection_command();
+        cmd.arg("-h");
+        println!("running: {:?}", cmd);
+        let help = match cmd.output() {
+            Ok(output) if output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            Ok(output) => {
+                eprintln!(
+                    "Warning: failed to list the options of the zig package, got: {}",
+                    output.status
+                );
+                None
+            }
+            Err(e) => {
+                eprintln!(
+                    "Warning: failed to list the options of the zig package: {}",
+                    e
+                );
+                None
+            }
+        };
+        self.build_help = Some((context, help.clone()));
+        help
+    }
 }
 
 /// Builds the native library rooted at `path` with the default zig options.
@@ -746,133 +2318,456 @@ pub fn build(path: impl AsRef<Path>) -> PathBuf {
     Build::new(path.as_ref()).build()
 }
 
-fn getenv_unwrap(v: &str) -> String {
-    match env::var(v) {
-        Ok(s) => s,
-        Err(..) => fail(&format!("environment variable `{}` not defined", v)),
-    }
-}
-
 fn fail(s: &str) -> ! {
     panic!("\n{}\n\nbuild failed, must exit now", s)
 }
 
-fn translate_target_triple() -> (&'static str, &'static str) {
-    // Read the target from the environment variables.
-    let target = getenv_unwrap("TARGET");
-
-    match &*target {
-        "aarch64-apple-darwin" => ("aarch64-macos", "aarch64"),
-        "aarch64-apple-ios" => ("aarch64-ios", "aarch64"),
-        "aarch64-apple-ios-macabi" => ("aarch64-ios-macabi", "aarch64"),
-        "aarch64-apple-ios-sim" => ("aarch64-ios-simulator", "aarch64"),
-        "aarch64-apple-tvos" => ("aarch64-tvos", "aarch64"),
-        "aarch64-apple-tvos-sim" => ("aarch64-tvos-simulator", "aarch64"),
-        "aarch64-apple-visionos" => ("aarch64-visionos", "aarch64"),
-        "aarch64-apple-visionos-sim" => ("aarch64-visionos-simulator", "aarch64"),
-        "aarch64-apple-watchos" => ("aarch64-watchos", "aarch64"),
-        "aarch64-apple-watchos-sim" => ("aarch64-watchos-simulator", "aarch64"),
-        "aarch64-linux-android" => ("aarch64-linux-android", "aarch64"),
-        "aarch64-pc-windows-gnullvm" => ("aarch64-windows-gnu", "aarch64"),
-        "aarch64-pc-windows-msvc" => ("aarch64-windows-msvc", "aarch64"),
-        "aarch64-unknown-freebsd" => ("aarch64-freebsd", "aarch64"),
-        "aarch64-unknown-fuchsia" => ("aarch64-fuchsia", "aarch64"),
-        "aarch64-unknown-hermit" => ("aarch64-hermit", "aarch64"),
-        "aarch64-unknown-illumos" => ("aarch64-illumos", "aarch64"),
-        "aarch64-unknown-linux-gnu" => ("aarch64-linux-gnu", "aarch64"),
-        "aarch64-unknown-linux-gnu_ilp32" => ("aarch64-linux-gnuilp32", "aarch64"),
-        "aarch64-unknown-linux-musl" => ("aarch64-linux-musl", "aarch64"),
-        "aarch64-unknown-linux-ohos" => ("aarch64-linux-ohos", "aarch64"),
-        "aarch64-unknown-netbsd" => ("aarch64-netbsd", "aarch64"),
-        "aarch64-unknown-none" => ("aarch64-freestanding", "aarch64"),
-        "aarch64-unknown-openbsd" => ("aarch64-openbsd", "aarch64"),
-        "aarch64-unknown-uefi" => ("aarch64-uefi", "aarch64"),
-        "aarch64_be-unknown-linux-gnu" => ("aarch64_be-linux-gnu", "aarch64_be"),
-        "aarch64_be-unknown-linux-gnu_ilp32" => ("aarch64_be-linux-gnuilp32", "aarch64_be"),
-        "aarch64_be-unknown-netbsd" => ("aarch64_be-netbsd", "aarch64_be"),
-        "bpfeb-unknown-none" => ("bpfeb-freestanding", "bpfeb"),
-        "bpfel-unknown-none" => ("bpfel-freestanding", "bpfel"),
-        "i386-apple-ios" => ("x86-ios", "x86"),
-        "i686-apple-darwin" => ("x86-macos", "x86"),
-        "i686-linux-android" => ("x86-linux-android", "x86"),
-        "i686-pc-windows-gnu" => ("x86-windows-gnu", "x86"),
-        "i686-pc-windows-gnullvm" => ("x86-windows-gnu", "x86"),
-        "i686-pc-windows-msvc" => ("x86-windows-msvc", "x86"),
-        "i686-unknown-freebsd" => ("x86-freebsd", "x86"),
-        "i686-unknown-haiku" => ("x86-haiku", "x86"),
-        "i686-unknown-hurd-gnu" => ("x86-hurd-gnu", "x86"),
-        "i686-unknown-linux-gnu" => ("x86-linux-gnu", "x86"),
-        "i686-unknown-linux-musl" => ("x86-linux-musl", "x86"),
-        "i686-unknown-netbsd" => ("x86-netbsd", "x86"),
-        "i686-unknown-openbsd" => ("x86-openbsd", "x86"),
-        "i686-unknown-uefi" => ("x86-uefi", "x86"),
-        "loongarch64-unknown-linux-gnu" => ("loongarch64-linux-gnu", "loongarch64"),
-        "loongarch64-unknown-linux-musl" => ("loongarch64-linux-musl", "loongarch64"),
-        "loongarch64-unknown-linux-ohos" => ("loongarch64-linux-ohos", "loongarch64"),
-        "loongarch64-unknown-none" => ("loongarch64-freestanding", "loongarch64"),
-        "x86_64-apple-darwin" => ("x86_64-macos", "x86_64"),
-        "x86_64-apple-ios" => ("x86_64-ios", "x86_64"),
-        "x86_64-apple-ios-macabi" => ("x86_64-ios-macabi", "x86_64"),
-        "x86_64-apple-tvos" => ("x86_64-tvos", "x86_64"),
-        "x86_64-apple-watchos-sim" => ("x86_64-watchos-simulator", "x86_64"),
-        "x86_64-linux-android" => ("x86_64-linux-android", "x86_64"),
-        "x86_64-pc-solaris" => ("x86_64-solaris", "x86_64"),
-        "x86_64-pc-windows-gnu" => ("x86_64-windows-gnu", "x86_64"),
-        "x86_64-pc-windows-gnullvm" => ("x86_64-windows-gnu", "x86_64"),
-        "x86_64-pc-windows-msvc" => ("x86_64-windows-msvc", "x86_64"),
-        "x86_64-unknown-dragonfly" => ("x86_64-dragonfly", "x86_64"),
-        "x86_64-unknown-freebsd" => ("x86_64-freebsd", "x86_64"),
-        "x86_64-unknown-fuchsia" => ("x86_64-fuchsia", "x86_64"),
-        "x86_64-unknown-haiku" => ("x86_64-haiku", "x86_64"),
-        "x86_64-unknown-hermit" => ("x86_64-hermit", "x86_64"),
-        "x86_64-unknown-hurd-gnu" => ("x86_64-hurd-gnu", "x86_64"),
-        "x86_64-unknown-illumos" => ("x86_64-illumos", "x86_64"),
-        "x86_64-unknown-linux-gnu" => ("x86_64-linux-gnu", "x86_64"),
-        "x86_64-unknown-linux-gnux32" => ("x86_64-linux-gnux32", "x86_64"),
-        "x86_64-unknown-linux-musl" => ("x86_64-linux-musl", "x86_64"),
-        "x86_64-unknown-linux-none" => ("x86_64-linux-none", "x86_64"),
-        "x86_64-unknown-linux-ohos" => ("x86_64-linux-ohos", "x86_64"),
-        "x86_64-unknown-netbsd" => ("x86_64-netbsd", "x86_64"),
-        "x86_64-unknown-none" => ("x86_64-freestanding", "x86_64"),
-        "x86_64-unknown-uefi" => ("x86_64-uefi", "x86_64"),
-        _ => panic!("target not supported {target:?}"),
+/// Runs `cmd`, returning its standard output on success.
+fn command_output(cmd: &mut Command) -> Option<String> {
+    println!("running: {:?}", cmd);
+    match cmd.output() {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        _ => None,
     }
 }
 
-fn translate_arch_feature(arch: &str, feature: &str) -> String {
-    let feature = feature.replace("-", "_").replace(".", "_");
-    match arch {
-        target if target.starts_with("aarch64") => translate_aarch64_target_feature(feature),
-        target if target.starts_with("x86") => translate_x86_target_feature(feature),
-        _ => feature,
-    }
+/// Extracts the major version from an LLVM version string, e.g. `19.1.7`.
+fn llvm_major_version(version: &str) -> Option<u32> {
+    let end = version
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(version.len());
+    version[..end].parse().ok()
 }
 
-fn translate_aarch64_target_feature(feature: String) -> String {
-    match &*feature {
-        "dpb" => "ccpp".to_string(),
-        "dpb2" => "ccdp".to_string(),
-        "fcma" => "complxnum".to_string(),
-        "fhm" => "fp16fml".to_string(),
-        "flagm2" => "altnzcv".to_string(),
-        "fp16" => "fullfp16".to_string(),
-        "frintts" => "fptoint".to_string(),
-        "paca" => "pauth".to_string(),
-        "pacg" => "pauth_lr".to_string(),
-        "pmuv3" => "perfmon".to_string(),
-        "rcpc2" => "rcpc_immo".to_string(),
-        _ => feature,
-    }
+/// Normalizes the name of a Cargo feature, as it appears in `CARGO_FEATURE_<name>`.
+fn normalize_feature(feature: &str) -> String {
+    feature.to_ascii_lowercase().replace('-', "_")
+}
+
+fn parse_target(target: &str) -> Result<Target> {
+    target
+        .parse()
+        .map_err(|e| Error::Config(format!("failed to parse zig target: {}", e)))
+}
+
+fn parse_cpu(cpu: &str) -> Result<Cpu> {
+    cpu.parse()
+        .map_err(|e| Error::Config(format!("failed to parse zig cpu: {}", e)))
 }
 
-fn translate_x86_target_feature(feature: String) -> String {
-    match &*feature {
-        "avx512vbmi1" => "avx512vbmi".to_string(),
-        "bmi1" => "bmi".to_string(),
-        "cmpxchg16b" => "cx16".to_string(),
-        "rdrand" => "rdrnd".to_string(),
-        "lahfsahf" => "sahf".to_string(),
-        "pclmulqdq" => "pclmul".to_string(),
-        _ => feature,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a build reading the environment variables `vars`, instead of the ones of the
+    /// process.
+    fn build_with_env(vars: &[(&str, &str)]) -> Build {
+        let mut build = Build::new("libfoo");
+        build.env_listed = true;
+        for (var, value) in vars {
+            build
+                .env_cache
+                .insert(var.to_string(), Some(OsString::from(value)));
+        }
+        build
+    }
+
+    #[test]
+    fn define_options() {
+        let mut build = Build::new("libfoo");
+        build
+            .define_bool("shared", true)
+            .unwrap()
+            .define_int("level", -3)
+            .unwrap()
+            .define_str("name", "a b=c")
+            .unwrap()
+            .define_enum("backend", "llvm")
+            .unwrap()
+            .define_list("modules", ["foo", "bar"])
+            .unwrap()
+            .define_bool("shared", false)
+            .unwrap();
+        assert_eq!(
+            build.options,
+            [
+                "-Dlevel=-3",
+                "-Dname=a b=c",
+                "-Dbackend=llvm",
+                "-Dmodules=foo",
+                "-Dmodules=bar",
+                "-Dshared=false",
+            ]
+        );
+
+        assert_eq!(
+            build.define_bool("optimize", true).err(),
+            Some(Error::ReservedOption("optimize".to_string()))
+        );
+        assert!(build.define_str("a=b", "c").is_err());
+        assert!(build.define_enum("backend", "not-a-tag").is_err());
+    }
+
+    #[test]
+    fn explicit_options_win() {
+        let mut build = build_with_env(&[(
+            "CARGO_ENCODED_RUSTFLAGS",
+            "-Cstrip=symbols\x1f-Ccode-model=small",
+        )]);
+        build
+            .codegen_option(CodegenOption::Strip)
+            .codegen_option(CodegenOption::CodeModel)
+            .define_bool("strip", false)
+            .unwrap();
+        assert_eq!(
+            build.derived_options(),
+            [("code-model".to_string(), "small".to_string())]
+        );
+
+        let mut build = build_with_env(&[("CARGO_CFG_SANITIZE", "address")]);
+        build.build_help = Some((
+            vec![],
+            Some(
+                "Project-Specific Options:\n  \
+                 -Dsanitize-address=[bool]  Enable ASan\n  \
+                 -Dsanitize-c=[enum]        Enable UBSan\n"
+                    .to_string(),
+            ),
+        ));
+        build.define_enum("sanitize-c", "trap").unwrap();
+        assert_eq!(
+            build.derived_options(),
+            [("sanitize-address".to_string(), "true".to_string())]
+        );
+
+        // The codegen option and the sanitizers agree on the type of `sanitize-c`.
+        build.options.clear();
+        build.codegen_option(CodegenOption::SanitizeC);
+        assert_eq!(
+            build.derived_options(),
+            [
+                ("sanitize-address".to_string(), "true".to_string()),
+                ("sanitize-c".to_string(), "full".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn introspect_configuration() {
+        let mut build = build_with_env(&[("ZIG", "zigcli-missing-zig")]);
+        assert_eq!(build.validate_options(), Ok(()));
+        assert!(build.build_help.is_none());
+
+        build
+            .target("x86_64-linux-gnu")
+            .cpu("x86_64_v3")
+            .define_bool("shared", false)
+            .unwrap();
+        let cmd = build.introspection_command();
+        let args = cmd
+            .get_args()
+            .map(|a| a.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            &args[..4],
+            [
+                "build",
+                "-Dtarget=x86_64-linux-gnu",
+                "-Dcpu=x86_64_v3",
+                "-Dshared=false"
+            ]
+        );
+
+        // The failure to list the options is only cached for the same target and CPU.
+        assert_eq!(build.project_options(), None);
+        assert_eq!(build.build_help.as_ref().unwrap().0, &args[1..3]);
+        build.target("aarch64-linux-gnu");
+        assert_ne!(
+            build.build_help.as_ref().unwrap().0,
+            build.introspection_context()
+        );
+    }
+
+    #[test]
+    fn cargo_config_vars() {
+        let mut build = build_with_env(&[
+            ("CARGO_CFG_TARGET_OS", "linux"),
+            ("CARGO_FEATURE_SIMD", "1"),
+            ("CARGO_PKG_NAME", "foo"),
+            ("CARGO_MAKEFLAGS", "-j"),
+            ("OUT_DIR", "/tmp"),
+        ]);
+        build
+            .env_cache
+            .insert("CARGO_FEATURE_STD".to_string(), None);
+        assert_eq!(
+            build.cargo_config_vars(),
+            [
+                ("CARGO_CFG_TARGET_OS".to_string(), "linux".to_string()),
+                ("CARGO_FEATURE_SIMD".to_string(), "1".to_string()),
+                ("CARGO_PKG_NAME".to_string(), "foo".to_string())
+            ]
+        );
+        assert!(build
+            .rerun_directives
+            .contains(&"cargo:rerun-if-env-changed=CARGO_FEATURE_SIMD".to_string()));
+    }
+
+    #[test]
+    fn forward_features() {
+        let vars = [
+            ("CARGO_FEATURE_DEFAULT", "1"),
+            ("CARGO_FEATURE_SIMD", "1"),
+            ("CARGO_FEATURE_FAST_MATH", "1"),
+            ("CARGO_FEATURE_STD", "1"),
+        ];
+        let help = "Project-Specific Options:\n  \
+                    -Dsimd=[bool]       Use SIMD\n  \
+                    -Dfeat-std=[bool]   Link libc\n  \
+                    -Dfeat-alloc=[bool] Use an allocator\n";
+
+        // Only the features declared as options are forwarded, except `default`.
+        let mut build = build_with_env(&vars);
+        build.build_help = Some((vec![], Some(help.to_string())));
+        build.forward_cargo_features();
+        build.forward_features().unwrap();
+        assert_eq!(build.options, ["-Dsimd=true"]);
+
+        // Features of the allow-list are always forwarded, and disabled ones as `false`.
+        let mut build = build_with_env(&vars);
+        build
+            .cargo_feature_prefix("feat-")
+            .allow_cargo_feature("std")
+            .allow_cargo_feature("alloc")
+            .define_bool("feat-alloc", true)
+            .unwrap();
+        build.forward_features().unwrap();
+        assert_eq!(build.options, ["-Dfeat-alloc=true", "-Dfeat-std=true"]);
+
+        let mut build = build_with_env(&vars);
+        build.cargo_features_list("features");
+        build.forward_features().unwrap();
+        assert_eq!(
+            build.options,
+            ["-Dfeatures=fast_math", "-Dfeatures=simd", "-Dfeatures=std"]
+        );
+    }
+
+    #[test]
+    fn env_options() {
+        let mut build = build_with_env(&[
+            ("TARGET", "x86_64-unknown-linux-gnu"),
+            (
+                "ZIGCLI_OPTIONS",
+                "-Dlevel=2 -Dfeature=a -Dfeature=b -Dstrip",
+            ),
+        ]);
+        build.define_int("level", 1).unwrap();
+        build.apply_env_overrides().unwrap();
+        assert_eq!(
+            build.options,
+            ["-Dlevel=2", "-Dfeature=a", "-Dfeature=b", "-Dstrip=true"]
+        );
+    }
+
+    #[test]
+    fn rerun_if_package_changed() {
+        let mut build = build_with_env(&[("TARGET", "x86_64-unknown-linux-gnu")]);
+        build.apply_env_overrides().unwrap();
+        let path = format!("cargo:rerun-if-changed={}", build.path.display());
+        assert_eq!(build.rerun_directives[0], path);
+        assert!(build
+            .rerun_directives
+            .contains(&"cargo:rerun-if-env-changed=ZIGCLI_OPTIONS".to_string()));
+
+        let mut build = build_with_env(&[("TARGET", "x86_64-unknown-linux-gnu")]);
+        build.rerun_if_changed("libfoo/src");
+        build.apply_env_overrides().unwrap();
+        let path = env::current_dir().unwrap().join("libfoo/src");
+        assert_eq!(
+            build.rerun_directives[0],
+            format!("cargo:rerun-if-changed={}", path.display())
+        );
+        assert!(!build
+            .rerun_directives
+            .iter()
+            .any(|d| d.ends_with(&*build.path.to_string_lossy())));
+    }
+
+    #[test]
+    fn invalid_env_options() {
+        let mut build = build_with_env(&[
+            ("TARGET", "x86_64-unknown-linux-gnu"),
+            ("ZIGCLI_OPTIONS", "-Dtarget=x86_64-linux"),
+        ]);
+        let Err(Error::Config(message)) = build.apply_env_overrides() else {
+            panic!("expected a configuration error");
+        };
+        assert!(message.starts_with("invalid value of `ZIGCLI_OPTIONS`"));
+
+        // Missing variables of Cargo are reported rather than panicking.
+        let mut build = build_with_env(&[("TARGET", "x86_64-unknown-linux-gnu")]);
+        assert_eq!(
+            build.command().unwrap_err(),
+            Error::Config("environment variable `OUT_DIR` not defined".to_string())
+        );
+    }
+
+    #[test]
+    fn resolve_command() {
+        let out_dir = env::temp_dir().join("zigcli-command");
+        let mut build = build_with_env(&[
+            ("OUT_DIR", out_dir.to_str().unwrap()),
+            ("TARGET", "x86_64-unknown-linux-gnu"),
+            ("PROFILE", "release"),
+            ("OPT_LEVEL", "3"),
+            ("DEBUG", "false"),
+            ("CARGO_CFG_TARGET_FEATURE", "fxsr,sse,sse2"),
+            ("ZIG", "zigcli-missing-zig"),
+        ]);
+
+        let args = build
+            .step("lib")
+            .jobs(2)
+            .maxrss(1 << 30)
+            .option("-Dshared=false")
+            .args()
+            .unwrap();
+        let args = args.iter().map(|a| a.to_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(&args[..2], ["build", "lib"]);
+        assert!(args.contains(&"-j2"));
+        assert!(args.contains(&"-Dtarget=x86_64-linux-gnu"));
+        assert!(args.contains(&"-Dshared=false"));
+        assert!(args.contains(&"-Dcpu=baseline+fxsr+sse+sse2"));
+        assert!(!args.contains(&"--skip-oom-steps"));
+    }
+
+    #[test]
+    fn target_cpu_keeps_abi_features() {
+        let out_dir = env::temp_dir().join("zigcli-command");
+        let mut build = build_with_env(&[
+            ("OUT_DIR", out_dir.to_str().unwrap()),
+            ("TARGET", "thumbv7em-none-eabi"),
+            ("PROFILE", "release"),
+            ("OPT_LEVEL", "s"),
+            ("DEBUG", "false"),
+            ("CARGO_CFG_TARGET_FEATURE", ""),
+            ("CARGO_ENCODED_RUSTFLAGS", "-Ctarget-cpu=cortex-m4"),
+            ("ZIG", "zigcli-missing-zig"),
+        ]);
+        let args = build.args().unwrap();
+        let cpu = args.iter().find_map(|a| a.to_str()?.strip_prefix("-Dcpu="));
+        assert_eq!(cpu, Some("cortex_m4+soft_float"));
+    }
+
+    #[test]
+    fn translate_deployment_target() {
+        let mut build = build_with_env(&[("MACOSX_DEPLOYMENT_TARGET", "11.0")]);
+        let (target, _) = build.translate_target("aarch64-apple-darwin").unwrap();
+        assert_eq!(target.to_string(), "aarch64-macos.11.0");
+        assert!(build
+            .rerun_directives
+            .contains(&"cargo:rerun-if-env-changed=MACOSX_DEPLOYMENT_TARGET".to_string()));
+
+        let mut build = build_with_env(&[("ZIGCLI_GLIBC_VERSION", "2.17")]);
+        let (target, _) = build.translate_target("x86_64-unknown-linux-gnu").unwrap();
+        assert_eq!(target.to_string(), "x86_64-linux-gnu.2.17");
+        assert!(build
+            .rerun_directives
+            .contains(&"cargo:rerun-if-env-changed=ZIGCLI_GLIBC_VERSION".to_string()));
+    }
+
+    #[test]
+    fn cached_profile_modes() {
+        let mut build = build_with_env(&[]);
+        let metadata = Value::parse("[profiles]\ndist = \"ReleaseSmall\"\n").unwrap();
+        build.manifest_metadata = Some(Some(metadata));
+        let mode = build.profile_mode("dist").unwrap().unwrap();
+        assert_eq!(mode.optimize, Some(Optimize::ReleaseSmall));
+        assert!(build.profile_mode("dev").unwrap().is_none());
+
+        build.profile_optimize("dist", Optimize::Debug);
+        let mode = build.profile_mode("dist").unwrap().unwrap();
+        assert_eq!(mode.optimize, Some(Optimize::Debug));
+    }
+
+    #[test]
+    fn explicit_cpu() {
+        let out_dir = env::temp_dir().join("zigcli-command");
+        let vars = [
+            ("OUT_DIR", out_dir.to_str().unwrap()),
+            ("TARGET", "x86_64-unknown-linux-gnu"),
+            ("PROFILE", "release"),
+            ("OPT_LEVEL", "3"),
+            ("DEBUG", "false"),
+            ("CARGO_CFG_TARGET_FEATURE", "fxsr,sse,sse2"),
+            ("ZIG", "zigcli-missing-zig"),
+        ];
+        let cpu = |build: &mut Build| {
+            let args = build.args().unwrap();
+            let cpu = args.iter().find_map(|a| a.to_str()?.strip_prefix("-Dcpu="));
+            cpu.map(str::to_string)
+        };
+
+        // An explicit model replaces the CPU derived from the Rust profile.
+        let mut build = build_with_env(&vars);
+        build.cpu("x86_64_v2").add_cpu("aes");
+        assert_eq!(cpu(&mut build).as_deref(), Some("x86_64_v2+aes"));
+
+        // Features alone are applied on top of it.
+        let mut build = build_with_env(&vars);
+        build.add_cpu("aes").remove_cpu("sse2");
+        assert_eq!(
+            cpu(&mut build).as_deref(),
+            Some("baseline+aes+fxsr+sse-sse2")
+        );
+    }
+
+    #[test]
+    fn linker_plugin_lto() {
+        for (rustflags, expected) in [
+            ("", false),
+            ("-Clinker-plugin-lto", true),
+            ("-C\x1flinker-plugin-lto=yes", true),
+            ("-Clinker-plugin-lto=/usr/lib/LLVMgold.so", true),
+            ("-Clinker-plugin-lto=off", false),
+            ("-Clinker-plugin-lto\x1f-Clinker-plugin-lto=n", false),
+            ("--codegen\x1flinker-plugin-lto=false", false),
+        ] {
+            let mut build = build_with_env(&[("CARGO_ENCODED_RUSTFLAGS", rustflags)]);
+            assert_eq!(build.rust_linker_plugin_lto(), expected, "{}", rustflags);
+        }
+    }
+
+    #[test]
+    fn target_cpu_option() {
+        for (rustflags, expected) in [
+            ("", None),
+            ("-Ctarget-cpu=native", Some("native")),
+            ("-C\x1ftarget-cpu=x86-64-v3", Some("x86-64-v3")),
+            ("--codegen=target-cpu=skylake", Some("skylake")),
+            (
+                "-Ctarget-cpu=native\x1f-Copt-level=3\x1f-Ctarget-cpu=znver4",
+                Some("znver4"),
+            ),
+            ("-Ctarget-feature=+avx2", None),
+        ] {
+            let mut build = build_with_env(&[("CARGO_ENCODED_RUSTFLAGS", rustflags)]);
+            let cpu = build.rustc_codegen_option("target-cpu");
+            assert_eq!(cpu.as_deref(), expected, "{}", rustflags);
+        }
+    }
+
+    #[test]
+    fn parse_llvm_major_version() {
+        assert_eq!(llvm_major_version("19.1.7"), Some(19));
+        assert_eq!(
+            llvm_major_version("20.1.2 (https://github.com/llvm/llvm-project)"),
+            Some(20)
+        );
+        assert_eq!(llvm_major_version("17"), Some(17));
+        assert_eq!(llvm_major_version("git"), None);
     }
 }
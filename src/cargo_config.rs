@@ -0,0 +1,265 @@
+//! Generation of a zig module exposing the Rust build configuration.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Configuration options of the Rust target which may hold multiple values.
+const LIST_CFGS: &[&str] = &[
+    "sanitize",
+    "target_family",
+    "target_feature",
+    "target_has_atomic",
+    "target_has_atomic_equal_alignment",
+    "target_has_atomic_load_store",
+];
+
+const ZIG_KEYWORDS: &[&str] = &[
+    "addrspace",
+    "align",
+    "allowzero",
+    "and",
+    "anyframe",
+    "anytype",
+    "asm",
+    "async",
+    "await",
+    "break",
+    "callconv",
+    "catch",
+    "comptime",
+    "const",
+    "continue",
+    "defer",
+    "else",
+    "enum",
+    "errdefer",
+    "error",
+    "export",
+    "extern",
+    "fn",
+    "for",
+    "if",
+    "inline",
+    "linksection",
+    "noalias",
+    "noinline",
+    "nosuspend",
+    "opaque",
+    "or",
+    "orelse",
+    "packed",
+    "pub",
+    "resume",
+    "return",
+    "struct",
+    "suspend",
+    "switch",
+    "test",
+    "threadlocal",
+    "try",
+    "union",
+    "unreachable",
+    "usingnamespace",
+    "var",
+    "volatile",
+    "while",
+];
+
+/// Names of the primitive types and values of zig, which declarations may not shadow, besides
+/// the integer types such as `u8` or `i128`.
+const ZIG_PRIMITIVES: &[&str] = &[
+    "anyerror",
+    "anyopaque",
+    "bool",
+    "c_char",
+    "c_int",
+    "c_long",
+    "c_longdouble",
+    "c_longlong",
+    "c_short",
+    "c_uint",
+    "c_ulong",
+    "c_ulonglong",
+    "c_ushort",
+    "comptime_float",
+    "comptime_int",
+    "f128",
+    "f16",
+    "f32",
+    "f64",
+    "f80",
+    "false",
+    "isize",
+    "noreturn",
+    "null",
+    "true",
+    "type",
+    "undefined",
+    "usize",
+    "void",
+];
+
+/// Generates the source of the `cargo_config.zig` module from the environment variables `vars`
+/// of the build script.
+///
+/// The `CARGO_CFG_*` variables are declared in the `cfg` namespace, the enabled
+/// `CARGO_FEATURE_*` variables in the `features` namespace, and the `CARGO_PKG_*` variables in
+/// the `pkg` namespace. Names are lowercase, e.g. `cfg.target_os` or `pkg.version_major`.
+pub(crate) fn generate(vars: impl IntoIterator<Item = (String, String)>) -> String {
+    let mut cfg = BTreeMap::new();
+    let mut features = BTreeMap::new();
+    let mut pkg = BTreeMap::new();
+    for (var, value) in vars {
+        if let Some(name) = var.strip_prefix("CARGO_CFG_") {
+            let name = name.to_ascii_lowercase();
+            let value = if LIST_CFGS.contains(&&*name) || value.contains(',') {
+                let values = value
+                    .split(',')
+                    .filter(|v| !v.is_empty())
+                    .map(zig_string)
+                    .collect::<Vec<_>>();
+                format!("&.{{ {} }}", values.join(", "))
+            } else if value.is_empty() {
+                "true".to_string()
+            } else {
+                zig_scalar(&value)
+            };
+            cfg.insert(name, value);
+        } else if let Some(name) = var.strip_prefix("CARGO_FEATURE_") {
+            features.insert(name.to_ascii_lowercase(), "true".to_string());
+        } else if let Some(name) = var.strip_prefix("CARGO_PKG_") {
+            pkg.insert(name.to_ascii_lowercase(), zig_scalar(&value));
+        }
+    }
+
+    let mut source = String::from("//! Rust build configuration, generated by zigcli.\n");
+    for (namespace, decls) in [("cfg", cfg), ("features", features), ("pkg", pkg)] {
+        writeln!(source, "\npub const {} = struct {{", namespace).unwrap();
+        for (name, value) in decls {
+            let ty = if value.starts_with("&.") {
+                ": []const []const u8"
+            } else {
+                ""
+            };
+            writeln!(
+                source,
+                "    pub const {}{} = {};",
+                zig_identifier(&name),
+                ty,
+                value
+            )
+            .unwrap();
+        }
+        source.push_str("};\n");
+    }
+    source
+}
+
+/// Formats `value` as an integer literal if it is a decimal number, or as a string literal.
+fn zig_scalar(value: &str) -> String {
+    if !value.is_empty() && value.len() < 20 && value.bytes().all(|b| b.is_ascii_digit()) {
+        value.to_string()
+    } else {
+        zig_string(value)
+    }
+}
+
+fn zig_string(value: &str) -> String {
+    let mut string = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            c if c.is_control() => write!(string, "\\u{{{:x}}}", c as u32).unwrap(),
+            c => string.push(c),
+        }
+    }
+    string.push('"');
+    string
+}
+
+fn zig_identifier(name: &str) -> String {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let integer_type = name.len() > 1
+        && name.starts_with(['i', 'u'])
+        && name[1..].bytes().all(|b| b.is_ascii_digit());
+    let reserved = name == "_"
+        || integer_type
+        || ZIG_KEYWORDS.contains(&name)
+        || ZIG_PRIMITIVES.contains(&name);
+    if valid && !reserved {
+        name.to_string()
+    } else {
+        format!("@{}", zig_string(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_module() {
+        let vars = [
+            ("CARGO_CFG_TARGET_OS", "linux"),
+            ("CARGO_CFG_TARGET_POINTER_WIDTH", "64"),
+            ("CARGO_CFG_TARGET_FEATURE", "fxsr,sse"),
+            ("CARGO_CFG_DEBUG_ASSERTIONS", ""),
+            ("CARGO_FEATURE_TEST", "1"),
+            ("CARGO_PKG_VERSION_MAJOR", "1"),
+            ("CARGO_PKG_DESCRIPTION", "A \"quoted\"\nline"),
+            ("PATH", "/usr/bin"),
+        ]
+        .map(|(var, value)| (var.to_string(), value.to_string()));
+        let source = generate(vars);
+        assert_eq!(
+            source,
+            r#"//! Rust build configuration, generated by zigcli.
+
+pub const cfg = struct {
+    pub const debug_assertions = true;
+    pub const target_feature: []const []const u8 = &.{ "fxsr", "sse" };
+    pub const target_os = "linux";
+    pub const target_pointer_width = 64;
+};
+
+pub const features = struct {
+    pub const @"test" = true;
+};
+
+pub const pkg = struct {
+    pub const description = "A \"quoted\"\nline";
+    pub const version_major = 1;
+};
+"#
+        );
+    }
+
+    #[test]
+    fn quote_identifiers() {
+        for name in [
+            "test",
+            "async",
+            "await",
+            "usingnamespace",
+            "i128",
+            "u8",
+            "bool",
+            "type",
+        ] {
+            assert_eq!(zig_identifier(name), format!("@\"{}\"", name));
+        }
+        for name in ["true", "false", "null", "undefined", "_", "2d", "foo-bar"] {
+            assert_eq!(zig_identifier(name), format!("@\"{}\"", name));
+        }
+        for name in ["simd", "i", "u8x", "int128", "_private"] {
+            assert_eq!(zig_identifier(name), name);
+        }
+    }
+}
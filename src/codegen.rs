@@ -0,0 +1,116 @@
+/// Zig build options which can be derived from the codegen settings of the current Rust profile.
+///
+/// The options are only passed to `zig build` when enabled with [`Build::codegen_option`], as the
+/// `build.zig` of the package must declare them.
+///
+/// [`Build::codegen_option`]: crate::Build::codegen_option
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum CodegenOption {
+    /// `-Dstrip=<bool>`, enabled by `-C strip=debuginfo|symbols`, or when the profile has no debug
+    /// info and `-C strip` is not set.
+    Strip,
+    /// `-Dpic=<bool>`, derived from `-C relocation-model`.
+    Pic,
+    /// `-Domit-frame-pointer=<bool>`, derived from `-C force-frame-pointers`.
+    OmitFramePointer,
+    /// `-Dcode-model=<model>`, derived from `-C code-model`.
+    CodeModel,
+    /// `-Dsanitize-c=<off|full>`, zig's C sanitization mode, `full` with debug assertions or
+    /// overflow checks.
+    SanitizeC,
+    /// `-Dunwind-tables=<bool>`, enabled by the `unwind` panic strategy.
+    UnwindTables,
+}
+
+impl CodegenOption {
+    /// Returns the conventional name of the option.
+    pub fn name(self) -> &'static str {
+        match self {
+            CodegenOption::Strip => "strip",
+            CodegenOption::Pic => "pic",
+            CodegenOption::OmitFramePointer => "omit-frame-pointer",
+            CodegenOption::CodeModel => "code-model",
+            CodegenOption::SanitizeC => "sanitize-c",
+            CodegenOption::UnwindTables => "unwind-tables",
+        }
+    }
+}
+
+/// Codegen settings of the current Rust profile.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RustCodegen {
+    pub(crate) debuginfo: bool,
+    pub(crate) debug_assertions: bool,
+    pub(crate) overflow_checks: bool,
+    pub(crate) panic_unwind: bool,
+    pub(crate) strip: Option<String>,
+    pub(crate) relocation_model: Option<String>,
+    pub(crate) code_model: Option<String>,
+    pub(crate) force_frame_pointers: Option<String>,
+    pub(crate) freestanding: bool,
+}
+
+impl RustCodegen {
+    /// Returns the value of the zig option `option`, if it can be derived.
+    pub(crate) fn value(&self, option: CodegenOption) -> Option<String> {
+        match option {
+            CodegenOption::Strip => {
+                let strip = match self.strip.as_deref() {
+                    Some(strip) => matches!(strip, "debuginfo" | "symbols"),
+                    None => !self.debuginfo,
+                };
+                Some(strip.to_string())
+            }
+            CodegenOption::Pic => {
+                let pic = match self.relocation_model.as_deref() {
+                    Some(model) => matches!(model, "pic" | "pie"),
+                    // Rust defaults to position independent code on hosted targets.
+                    None => !self.freestanding,
+                };
+                Some(pic.to_string())
+            }
+            CodegenOption::OmitFramePointer => {
+                let force = matches!(
+                    self.force_frame_pointers.as_deref()?,
+                    "" | "y" | "yes" | "on" | "true" | "always" | "non-leaf"
+                );
+                Some((!force).to_string())
+            }
+            CodegenOption::CodeModel => self.code_model.clone(),
+            CodegenOption::SanitizeC => {
+                let checked = self.debug_assertions || self.overflow_checks;
+                Some(if checked { "full" } else { "off" }.to_string())
+            }
+            CodegenOption::UnwindTables => Some(self.panic_unwind.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_values() {
+        let mut codegen = RustCodegen::default();
+        assert_eq!(codegen.value(CodegenOption::Strip).unwrap(), "true");
+        assert_eq!(codegen.value(CodegenOption::SanitizeC).unwrap(), "off");
+        codegen.strip = Some("none".to_string());
+        assert_eq!(codegen.value(CodegenOption::Strip).unwrap(), "false");
+        codegen.strip = Some("symbols".to_string());
+        codegen.debuginfo = true;
+        assert_eq!(codegen.value(CodegenOption::Strip).unwrap(), "true");
+        codegen.strip = None;
+        assert_eq!(codegen.value(CodegenOption::Strip).unwrap(), "false");
+
+        codegen.overflow_checks = true;
+        assert_eq!(codegen.value(CodegenOption::SanitizeC).unwrap(), "full");
+        assert_eq!(codegen.value(CodegenOption::CodeModel), None);
+        assert_eq!(codegen.value(CodegenOption::OmitFramePointer), None);
+        codegen.force_frame_pointers = Some(String::new());
+        assert_eq!(
+            codegen.value(CodegenOption::OmitFramePointer).unwrap(),
+            "false"
+        );
+    }
+}
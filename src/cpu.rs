@@ -0,0 +1,973 @@
+use std::{collections::BTreeSet, error::Error, fmt, str::FromStr};
+
+/// A Zig CPU model, together with the features to enable and disable on top of it.
+///
+/// The CPU is formatted with the syntax expected by `-Dcpu`, e.g. `cortex_m4+soft_float` or
+/// `native-avx512f`. Features have set semantics: adding a feature after removing it, or vice
+/// versa, cancels both operations out.
+///
+/// # Examples
+///
+/// ```
+/// use zigcli::Cpu;
+///
+/// let mut cpu = Cpu::new("x86_64_v3");
+/// cpu.add("aes").add("avx2").remove("avx2").remove("sse4_2");
+/// assert_eq!(cpu.to_string(), "x86_64_v3+aes-sse4_2");
+/// ```
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct Cpu {
+    model: Option<String>,
+    add: BTreeSet<String>,
+    remove: BTreeSet<String>,
+}
+
+/// Error returned when parsing a [`Cpu`] fails.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseCpuError {
+    message: String,
+}
+
+impl Cpu {
+    /// Creates a CPU with the model `model` and no additional features.
+    ///
+    /// # Panics
+    ///
+    /// The model name may only contain ASCII alphanumeric characters and underscores.
+    pub fn new(model: impl Into<String>) -> Self {
+        let mut cpu = Self::default();
+        cpu.set_model(model);
+        cpu
+    }
+
+    /// Returns the CPU model, if set.
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Returns the enabled features.
+    pub fn added(&self) -> impl Iterator<Item = &str> {
+        self.add.iter().map(String::as_str)
+    }
+
+    /// Returns the disabled features.
+    pub fn removed(&self) -> impl Iterator<Item = &str> {
+        self.remove.iter().map(String::as_str)
+    }
+
+    /// Sets the CPU model.
+    ///
+    /// # Panics
+    ///
+    /// The model name may only contain ASCII alphanumeric characters and underscores.
+    pub fn set_model(&mut self, model: impl Into<String>) -> &mut Self {
+        let model = model.into();
+        if let Err(e) = validate_name("CPU model", &model) {
+            panic!("{}", e);
+        }
+        self.model = Some(model);
+        self
+    }
+
+    /// Enables the feature `feature`, or cancels out its removal.
+    ///
+    /// # Panics
+    ///
+    /// The feature name may only contain ASCII alphanumeric characters and underscores.
+    pub fn add(&mut self, feature: impl Into<String>) -> &mut Self {
+        let feature = feature.into();
+        if let Err(e) = validate_name("CPU feature", &feature) {
+            panic!("{}", e);
+        }
+        if !self.remove.remove(&feature) {
+            self.add.insert(feature);
+        }
+        self
+    }
+
+    /// Disables the feature `feature`, or cancels out its addition.
+    ///
+    /// # Panics
+    ///
+    /// The feature name may only contain ASCII alphanumeric characters and underscores.
+    pub fn remove(&mut self, feature: impl Into<String>) -> &mut Self {
+        let feature = feature.into();
+        if let Err(e) = validate_name("CPU feature", &feature) {
+            panic!("{}", e);
+        }
+        if !self.add.remove(&feature) {
+            self.remove.insert(feature);
+        }
+        self
+    }
+
+    /// Applies the configuration of `other` on top of this CPU.
+    ///
+    /// The model of `other` takes precedence, if set, and its features override the ones of this
+    /// CPU. Unlike with [`Cpu::remove`], a feature removed by `other` stays disabled even if this
+    /// CPU enables it, as the model may enable it implicitly.
+    pub fn merge(&mut self, other: &Cpu) -> &mut Self {
+        if let Some(model) = &other.model {
+            self.model = Some(model.clone());
+        }
+        for feature in &other.add {
+            self.remove.remove(feature);
+            self.add.insert(feature.clone());
+        }
+        for feature in &other.remove {
+            self.add.remove(feature);
+            self.remove.insert(feature.clone());
+        }
+        self
+    }
+}
+
+impl FromStr for Cpu {
+    type Err = ParseCpuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let end = s.find(['+', '-']).unwrap_or(s.len());
+        let mut cpu = Cpu::default();
+        if end != 0 {
+            validate_name("CPU model", &s[..end])?;
+            cpu.model = Some(s[..end].to_string());
+        }
+
+        let mut rest = &s[end..];
+        while let Some(sign) = rest.chars().next() {
+            rest = &rest[1..];
+            let end = rest.find(['+', '-']).unwrap_or(rest.len());
+            let feature = &rest[..end];
+            validate_name("CPU feature", feature)?;
+            if sign == '+' {
+                cpu.add(feature);
+            } else {
+                cpu.remove(feature);
+            }
+            rest = &rest[end..];
+        }
+        Ok(cpu)
+    }
+}
+
+impl TryFrom<&str> for Cpu {
+    type Error = ParseCpuError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for Cpu {
+    type Error = ParseCpuError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Cpu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.model.as_deref().unwrap_or("baseline"))?;
+        for feature in &self.add {
+            write!(f, "+{feature}")?;
+        }
+        for feature in &self.remove {
+            write!(f, "-{feature}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseCpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ParseCpuError {}
+
+fn validate_name(kind: &str, name: &str) -> Result<(), ParseCpuError> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Ok(())
+    } else {
+        Err(ParseCpuError {
+            message: format!("invalid {kind} name `{name}`"),
+        })
+    }
+}
+
+/// Returns the zig CPU model matching the architecture version and float ABI implied by the Rust
+/// target triple.
+pub(crate) fn translate_target_cpu_model(target: &str) -> &'static str {
+    match target {
+        "arm-linux-androideabi" => "generic+v5te+soft_float",
+        "arm-unknown-linux-gnueabi" => "generic+v6+soft_float",
+        "arm-unknown-linux-gnueabihf" => "generic+v6+vfp2",
+        "arm-unknown-linux-musleabi" => "generic+v6+soft_float",
+        "arm-unknown-linux-musleabihf" => "generic+v6+vfp2",
+        "armebv7r-none-eabi" => "cortex_r4",
+        "armebv7r-none-eabihf" => "cortex_r4f",
+        "armv4t-unknown-linux-gnueabi" => "generic+v4t+soft_float",
+        "armv5te-unknown-linux-gnueabi" => "generic+v5te+soft_float",
+        "armv5te-unknown-linux-musleabi" => "generic+v5te+soft_float",
+        "armv6-unknown-freebsd" => "generic+v6+vfp2",
+        "armv6-unknown-netbsd-eabihf" => "generic+v6+vfp2",
+        "armv7-linux-androideabi" => "generic+v7a+vfp3d16",
+        "armv7-unknown-freebsd" => "generic+v7a+vfp3d16",
+        "armv7-unknown-linux-gnueabi" => "generic+v7a+soft_float",
+        "armv7-unknown-linux-gnueabihf" => "generic+v7a+vfp3d16",
+        "armv7-unknown-linux-musleabi" => "generic+v7a+soft_float",
+        "armv7-unknown-linux-musleabihf" => "generic+v7a+vfp3d16",
+        "armv7-unknown-netbsd-eabihf" => "generic+v7a+vfp3d16",
+        "armv7a-none-eabi" => "generic+v7a+soft_float",
+        "armv7a-none-eabihf" => "generic+v7a+vfp3d16",
+        "armv7r-none-eabi" => "cortex_r4",
+        "armv7r-none-eabihf" => "cortex_r4f",
+        "armv8r-none-eabihf" => "cortex_r52",
+        "i586-unknown-linux-gnu" => "pentium",
+        "i586-unknown-linux-musl" => "pentium",
+        "mips-unknown-linux-gnu" => "mips32r2",
+        "mips-unknown-linux-musl" => "mips32r2",
+        "mips64-unknown-linux-gnuabi64" => "mips64r2",
+        "mips64-unknown-linux-muslabi64" => "mips64r2",
+        "mips64el-unknown-linux-gnuabi64" => "mips64r2",
+        "mips64el-unknown-linux-muslabi64" => "mips64r2",
+        "mipsel-unknown-linux-gnu" => "mips32r2",
+        "mipsel-unknown-linux-musl" => "mips32r2",
+        "mipsel-unknown-netbsd" => "mips32",
+        "mipsel-unknown-none" => "mips32r2",
+        "mipsisa32r6-unknown-linux-gnu" => "mips32r6",
+        "mipsisa32r6el-unknown-linux-gnu" => "mips32r6",
+        "mipsisa64r6-unknown-linux-gnuabi64" => "mips64r6",
+        "mipsisa64r6el-unknown-linux-gnuabi64" => "mips64r6",
+        "powerpc-unknown-linux-gnuspe" => "e500",
+        "powerpc64-ibm-aix" => "pwr7",
+        "powerpc64le-unknown-freebsd" => "ppc64le",
+        "powerpc64le-unknown-linux-gnu" => "ppc64le",
+        "powerpc64le-unknown-linux-musl" => "ppc64le",
+        "riscv32gc-unknown-linux-gnu" => "baseline_rv32",
+        "riscv32gc-unknown-linux-musl" => "baseline_rv32",
+        "riscv32i-unknown-none-elf" => "generic_rv32",
+        "riscv32im-unknown-none-elf" => "generic_rv32+m",
+        "riscv32ima-unknown-none-elf" => "generic_rv32+m+a",
+        "riscv32imac-unknown-none-elf" => "generic_rv32+m+a+c",
+        "riscv32imafc-unknown-none-elf" => "generic_rv32+m+a+f+c",
+        "riscv32imc-unknown-none-elf" => "generic_rv32+m+c",
+        "riscv64-linux-android" => "baseline_rv64",
+        "riscv64gc-unknown-freebsd" => "baseline_rv64",
+        "riscv64gc-unknown-fuchsia" => "baseline_rv64",
+        "riscv64gc-unknown-hermit" => "baseline_rv64",
+        "riscv64gc-unknown-linux-gnu" => "baseline_rv64",
+        "riscv64gc-unknown-linux-musl" => "baseline_rv64",
+        "riscv64gc-unknown-netbsd" => "baseline_rv64",
+        "riscv64gc-unknown-none-elf" => "baseline_rv64",
+        "riscv64gc-unknown-openbsd" => "baseline_rv64",
+        "riscv64imac-unknown-none-elf" => "generic_rv64+m+a+c",
+        "s390x-unknown-linux-gnu" => "z10",
+        "s390x-unknown-linux-musl" => "z10",
+        "sparc-unknown-linux-gnu" => "v9",
+        "sparc-unknown-none-elf" => "leon3",
+        "sparc64-unknown-linux-gnu" => "v9",
+        "sparc64-unknown-netbsd" => "v9",
+        "sparc64-unknown-openbsd" => "v9",
+        "sparcv9-sun-solaris" => "v9",
+        "thumbv4t-none-eabi" => "generic+v4t+soft_float",
+        "thumbv5te-none-eabi" => "generic+v5te+soft_float",
+        "thumbv6m-none-eabi" => "cortex_m0plus",
+        "thumbv7a-pc-windows-msvc" => "generic+v7a+neon",
+        "thumbv7em-none-eabi" => "cortex_m4+soft_float",
+        "thumbv7em-none-eabihf" => "cortex_m4",
+        "thumbv7m-none-eabi" => "cortex_m3",
+        "thumbv7neon-linux-androideabi" => "generic+v7a+neon",
+        "thumbv7neon-unknown-linux-gnueabihf" => "generic+v7a+neon",
+        "thumbv8m.base-none-eabi" => "cortex_m23",
+        "thumbv8m.main-none-eabi" => "cortex_m33+soft_float",
+        "thumbv8m.main-none-eabihf" => "cortex_m33",
+        "wasm32-unknown-emscripten" => "generic",
+        "wasm32-unknown-unknown" => "generic",
+        "wasm32-wasi" => "generic",
+        "wasm32-wasip1" => "generic",
+        "wasm32-wasip1-threads" => "generic",
+        "wasm32-wasip2" => "generic",
+        "wasm32v1-none" => "mvp",
+        "wasm64-unknown-unknown" => "generic",
+        _ => "baseline",
+    }
+}
+
+pub(crate) fn translate_arch_cpu(arch: &str, cpu: &str) -> String {
+    let cpu = cpu.replace("-", "_").replace(".", "_");
+    match arch {
+        target if target.starts_with("aarch64") => translate_aarch64_target_cpu(cpu),
+        _ => cpu,
+    }
+}
+
+fn translate_aarch64_target_cpu(cpu: String) -> String {
+    match &*cpu {
+        "cyclone" => "apple_a7".to_string(),
+        _ => cpu,
+    }
+}
+
+pub(crate) fn translate_arch_feature(arch: &str, feature: &str) -> Option<String> {
+    // `crt-static` is a Rust-specific feature without an LLVM counterpart.
+    if feature.is_empty() || feature == "crt-static" {
+        return None;
+    }
+
+    let feature = feature.replace("-", "_").replace(".", "_");
+    match arch {
+        target if target.starts_with("aarch64") => Some(translate_aarch64_target_feature(feature)),
+        target if target.starts_with("x86") => Some(translate_x86_target_feature(feature)),
+        "arm" | "armeb" | "thumb" | "thumbeb" => translate_arm_target_feature(feature),
+        target if target.starts_with("riscv") => translate_riscv_target_feature(feature),
+        target if target.starts_with("wasm") => translate_wasm_target_feature(feature),
+        target if target.starts_with("powerpc") => translate_powerpc_target_feature(feature),
+        target if target.starts_with("loongarch") => translate_loongarch_target_feature(feature),
+        _ => Some(feature),
+    }
+}
+
+fn translate_aarch64_target_feature(feature: String) -> String {
+    match &*feature {
+        "dpb" => "ccpp".to_string(),
+        "dpb2" => "ccdp".to_string(),
+        "fcma" => "complxnum".to_string(),
+        "fhm" => "fp16fml".to_string(),
+        "flagm2" => "altnzcv".to_string(),
+        "fp16" => "fullfp16".to_string(),
+        "frintts" => "fptoint".to_string(),
+        "paca" => "pauth".to_string(),
+        "pacg" => "pauth_lr".to_string(),
+        "pmuv3" => "perfmon".to_string(),
+        "rcpc2" => "rcpc_immo".to_string(),
+        _ => feature,
+    }
+}
+
+fn translate_x86_target_feature(feature: String) -> String {
+    match &*feature {
+        "avx512vbmi1" => "avx512vbmi".to_string(),
+        "bmi1" => "bmi".to_string(),
+        "cmpxchg16b" => "cx16".to_string(),
+        "rdrand" => "rdrnd".to_string(),
+        "lahfsahf" => "sahf".to_string(),
+        "pclmulqdq" => "pclmul".to_string(),
+        _ => feature,
+    }
+}
+
+fn translate_arm_target_feature(feature: String) -> Option<String> {
+    match &*feature {
+        "aclass" | "aes" | "crc" | "d32" | "dotprod" | "dsp" | "fp_armv8" | "fpregs" | "i8mm"
+        | "mclass" | "neon" | "rclass" | "sha2" | "thumb2" | "trustzone" | "vfp2" | "vfp3"
+        | "vfp4" | "virtualization" => Some(feature),
+        "fp16" => Some("fullfp16".to_string()),
+        "v5te" => Some("has_v5te".to_string()),
+        "v6" => Some("has_v6".to_string()),
+        "v6k" => Some("has_v6k".to_string()),
+        "v6t2" => Some("has_v6t2".to_string()),
+        "v7" => Some("has_v7".to_string()),
+        "v8" => Some("has_v8".to_string()),
+        // Zig selects the instruction set through the `arm` and `thumb` architectures.
+        "thumb_mode" => None,
+        _ => ignore_unknown_feature("arm", feature),
+    }
+}
+
+fn translate_riscv_target_feature(feature: String) -> Option<String> {
+    match &*feature {
+        "a"
+        | "c"
+        | "d"
+        | "e"
+        | "f"
+        | "m"
+        | "relax"
+        | "unaligned_scalar_mem"
+        | "v"
+        | "za128rs"
+        | "za64rs"
+        | "zaamo"
+        | "zabha"
+        | "zacas"
+        | "zalrsc"
+        | "zama16b"
+        | "zawrs"
+        | "zba"
+        | "zbb"
+        | "zbc"
+        | "zbkb"
+        | "zbkc"
+        | "zbkx"
+        | "zbs"
+        | "zdinx"
+        | "zfa"
+        | "zfbfmin"
+        | "zfh"
+        | "zfhmin"
+        | "zfinx"
+        | "zhinx"
+        | "zhinxmin"
+        | "zic64b"
+        | "zicbom"
+        | "zicbop"
+        | "zicboz"
+        | "ziccamoa"
+        | "ziccif"
+        | "zicclsm"
+        | "ziccrse"
+        | "zicntr"
+        | "zicond"
+        | "zicsr"
+        | "zifencei"
+        | "zihintntl"
+        | "zihintpause"
+        | "zihpm"
+        | "zimop"
+        | "zk"
+        | "zkn"
+        | "zknd"
+        | "zkne"
+        | "zknh"
+        | "zkr"
+        | "zks"
+        | "zksed"
+        | "zksh"
+        | "zkt"
+        | "ztso"
+        | "zvbb"
+        | "zvbc"
+        | "zve32f"
+        | "zve32x"
+        | "zve64d"
+        | "zve64f"
+        | "zve64x"
+        | "zvfh"
+        | "zvfhmin"
+        | "zvkb"
+        | "zvkg"
+        | "zvkn"
+        | "zvknc"
+        | "zvkned"
+        | "zvkng"
+        | "zvknha"
+        | "zvknhb"
+        | "zvks"
+        | "zvksc"
+        | "zvksed"
+        | "zvksg"
+        | "zvksh"
+        | "zvkt" => Some(feature),
+        "fast_unaligned_access" => Some("unaligned_scalar_mem".to_string()),
+        _ => ignore_unknown_feature("riscv", feature),
+    }
+}
+
+fn translate_wasm_target_feature(feature: String) -> Option<String> {
+    match &*feature {
+        "atomics"
+        | "bulk_memory"
+        | "exception_handling"
+        | "extended_const"
+        | "multimemory"
+        | "multivalue"
+        | "mutable_globals"
+        | "nontrapping_fptoint"
+        | "reference_types"
+        | "relaxed_simd"
+        | "sign_ext"
+        | "simd128"
+        | "tail_call"
+        | "wide_arithmetic" => Some(feature),
+        _ => ignore_unknown_feature("wasm", feature),
+    }
+}
+
+fn translate_powerpc_target_feature(feature: String) -> Option<String> {
+    match &*feature {
+        "altivec" | "msync" | "partword_atomics" | "power10_vector" | "power8_altivec"
+        | "power8_vector" | "power9_altivec" | "power9_vector" | "quadword_atomics" | "vsx" => {
+            Some(feature)
+        }
+        "power8_crypto" => Some("crypto".to_string()),
+        _ => ignore_unknown_feature("powerpc", feature),
+    }
+}
+
+fn translate_loongarch_target_feature(feature: String) -> Option<String> {
+    match &*feature {
+        "d" | "div32" | "f" | "frecipe" | "lam_bh" | "lamcas" | "lasx" | "lbt" | "ld_seq_sa"
+        | "lsx" | "lvz" | "relax" | "scq" | "ual" => Some(feature),
+        _ => ignore_unknown_feature("loongarch", feature),
+    }
+}
+
+fn ignore_unknown_feature(arch: &str, feature: String) -> Option<String> {
+    eprintln!(
+        "Warning: unknown {} target feature={}; ignoring it.",
+        arch, feature
+    );
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZIG_ARM_FEATURES: &[&str] = &[
+        "aclass",
+        "aes",
+        "crc",
+        "crypto",
+        "d32",
+        "dotprod",
+        "dsp",
+        "fp16",
+        "fp64",
+        "fp_armv8",
+        "fp_armv8d16",
+        "fpregs",
+        "fullfp16",
+        "has_v4t",
+        "has_v5t",
+        "has_v5te",
+        "has_v6",
+        "has_v6k",
+        "has_v6m",
+        "has_v6t2",
+        "has_v7",
+        "has_v7clrex",
+        "has_v8",
+        "i8mm",
+        "mclass",
+        "mp",
+        "neon",
+        "rclass",
+        "sha2",
+        "soft_float",
+        "thumb2",
+        "thumb_mode",
+        "trustzone",
+        "v4t",
+        "v5te",
+        "v6",
+        "v6k",
+        "v6m",
+        "v7a",
+        "v7em",
+        "v7m",
+        "v7r",
+        "v8a",
+        "v8m",
+        "v8m_main",
+        "v8r",
+        "vfp2",
+        "vfp2sp",
+        "vfp3",
+        "vfp3d16",
+        "vfp4",
+        "vfp4d16",
+        "virtualization",
+    ];
+
+    const ZIG_RISCV_FEATURES: &[&str] = &[
+        "32bit",
+        "64bit",
+        "a",
+        "c",
+        "d",
+        "e",
+        "f",
+        "forced_atomics",
+        "i",
+        "m",
+        "relax",
+        "unaligned_scalar_mem",
+        "unaligned_vector_mem",
+        "v",
+        "za128rs",
+        "za64rs",
+        "zaamo",
+        "zabha",
+        "zacas",
+        "zalrsc",
+        "zama16b",
+        "zawrs",
+        "zba",
+        "zbb",
+        "zbc",
+        "zbkb",
+        "zbkc",
+        "zbkx",
+        "zbs",
+        "zca",
+        "zcb",
+        "zcd",
+        "zcf",
+        "zcmop",
+        "zdinx",
+        "zfa",
+        "zfbfmin",
+        "zfh",
+        "zfhmin",
+        "zfinx",
+        "zhinx",
+        "zhinxmin",
+        "zic64b",
+        "zicbom",
+        "zicbop",
+        "zicboz",
+        "ziccamoa",
+        "ziccif",
+        "zicclsm",
+        "ziccrse",
+        "zicntr",
+        "zicond",
+        "zicsr",
+        "zifencei",
+        "zihintntl",
+        "zihintpause",
+        "zihpm",
+        "zimop",
+        "zk",
+        "zkn",
+        "zknd",
+        "zkne",
+        "zknh",
+        "zkr",
+        "zks",
+        "zksed",
+        "zksh",
+        "zkt",
+        "zmmul",
+        "ztso",
+        "zvbb",
+        "zvbc",
+        "zve32f",
+        "zve32x",
+        "zve64d",
+        "zve64f",
+        "zve64x",
+        "zvfh",
+        "zvfhmin",
+        "zvkb",
+        "zvkg",
+        "zvkn",
+        "zvknc",
+        "zvkned",
+        "zvkng",
+        "zvknha",
+        "zvknhb",
+        "zvks",
+        "zvksc",
+        "zvksed",
+        "zvksg",
+        "zvksh",
+        "zvkt",
+        "zvl128b",
+        "zvl256b",
+        "zvl32b",
+        "zvl64b",
+    ];
+
+    const ZIG_WASM_FEATURES: &[&str] = &[
+        "atomics",
+        "bulk_memory",
+        "bulk_memory_opt",
+        "call_indirect_overlong",
+        "exception_handling",
+        "extended_const",
+        "fp16",
+        "multimemory",
+        "multivalue",
+        "mutable_globals",
+        "nontrapping_fptoint",
+        "reference_types",
+        "relaxed_simd",
+        "sign_ext",
+        "simd128",
+        "tail_call",
+        "wide_arithmetic",
+    ];
+
+    const ZIG_POWERPC_FEATURES: &[&str] = &[
+        "64bit",
+        "altivec",
+        "crypto",
+        "direct_move",
+        "fpcvt",
+        "hard_float",
+        "htm",
+        "isel",
+        "msync",
+        "partword_atomics",
+        "power10_vector",
+        "power8_altivec",
+        "power8_vector",
+        "power9_altivec",
+        "power9_vector",
+        "quadword_atomics",
+        "spe",
+        "vsx",
+    ];
+
+    const ZIG_LOONGARCH_FEATURES: &[&str] = &[
+        "32bit",
+        "64bit",
+        "d",
+        "div32",
+        "f",
+        "frecipe",
+        "lam_bh",
+        "lamcas",
+        "lasx",
+        "lbt",
+        "ld_seq_sa",
+        "lsx",
+        "lvz",
+        "prefer_w_inst",
+        "relax",
+        "scq",
+        "ual",
+    ];
+
+    #[test]
+    fn parse_and_merge() {
+        let mut cpu: Cpu = "generic+v7a+vfp3d16-neon".parse().unwrap();
+        assert_eq!(cpu.model(), Some("generic"));
+        assert_eq!(cpu.to_string(), "generic+v7a+vfp3d16-neon");
+
+        cpu.merge(&"cortex_a8+neon-vfp3d16".parse().unwrap());
+        assert_eq!(cpu.to_string(), "cortex_a8+neon+v7a-vfp3d16");
+
+        let mut cpu = Cpu::default();
+        cpu.remove("avx").add("avx").remove("sse4_1");
+        assert_eq!(cpu.model(), None);
+        assert_eq!(cpu.to_string(), "baseline-sse4_1");
+
+        assert!("native+".parse::<Cpu>().is_err());
+        assert!("native+avx.512".parse::<Cpu>().is_err());
+    }
+
+    #[test]
+    fn translate_target_cpu() {
+        for (arch, rust_cpu, zig_cpu) in [
+            ("x86_64", "x86-64-v3", "x86_64_v3"),
+            ("x86_64", "native", "native"),
+            ("x86", "pentium4", "pentium4"),
+            ("aarch64", "cyclone", "apple_a7"),
+            ("aarch64", "apple-m1", "apple_m1"),
+            ("aarch64", "neoverse-n1", "neoverse_n1"),
+            ("thumb", "cortex-m4", "cortex_m4"),
+            ("riscv64", "sifive-u74", "sifive_u74"),
+            ("powerpc64le", "pwr9", "pwr9"),
+        ] {
+            assert_eq!(translate_arch_cpu(arch, rust_cpu), zig_cpu);
+        }
+    }
+
+    fn check_features(arch: &str, rust_features: &[&str], zig_features: &[&str]) {
+        for &rust_feature in rust_features {
+            if let Some(feature) = translate_arch_feature(arch, rust_feature) {
+                assert!(
+                    zig_features.contains(&&*feature),
+                    "{arch} feature `{rust_feature}` translated to unknown zig feature `{feature}`"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn arm_features() {
+        let rust_features = [
+            "aclass",
+            "aes",
+            "crc",
+            "d32",
+            "dotprod",
+            "dsp",
+            "fp-armv8",
+            "fp16",
+            "fpregs",
+            "i8mm",
+            "mclass",
+            "neon",
+            "rclass",
+            "sha2",
+            "thumb-mode",
+            "thumb2",
+            "trustzone",
+            "v5te",
+            "v6",
+            "v6k",
+            "v6t2",
+            "v7",
+            "v8",
+            "vfp2",
+            "vfp3",
+            "vfp4",
+            "virtualization",
+        ];
+        check_features("arm", &rust_features, ZIG_ARM_FEATURES);
+        check_features("thumb", &rust_features, ZIG_ARM_FEATURES);
+        assert_eq!(translate_arch_feature("thumb", "thumb-mode"), None);
+    }
+
+    #[test]
+    fn riscv_features() {
+        let rust_features = [
+            "a",
+            "c",
+            "d",
+            "e",
+            "f",
+            "fast-unaligned-access",
+            "m",
+            "relax",
+            "unaligned-scalar-mem",
+            "v",
+            "za128rs",
+            "za64rs",
+            "zaamo",
+            "zabha",
+            "zacas",
+            "zalrsc",
+            "zama16b",
+            "zawrs",
+            "zba",
+            "zbb",
+            "zbc",
+            "zbkb",
+            "zbkc",
+            "zbkx",
+            "zbs",
+            "zdinx",
+            "zfa",
+            "zfbfmin",
+            "zfh",
+            "zfhmin",
+            "zfinx",
+            "zhinx",
+            "zhinxmin",
+            "zic64b",
+            "zicbom",
+            "zicbop",
+            "zicboz",
+            "ziccamoa",
+            "ziccif",
+            "zicclsm",
+            "ziccrse",
+            "zicntr",
+            "zicond",
+            "zicsr",
+            "zifencei",
+            "zihintntl",
+            "zihintpause",
+            "zihpm",
+            "zimop",
+            "zk",
+            "zkn",
+            "zknd",
+            "zkne",
+            "zknh",
+            "zkr",
+            "zks",
+            "zksed",
+            "zksh",
+            "zkt",
+            "ztso",
+            "zvbb",
+            "zvbc",
+            "zve32f",
+            "zve32x",
+            "zve64d",
+            "zve64f",
+            "zve64x",
+            "zvfh",
+            "zvfhmin",
+            "zvkb",
+            "zvkg",
+            "zvkn",
+            "zvknc",
+            "zvkned",
+            "zvkng",
+            "zvknha",
+            "zvknhb",
+            "zvks",
+            "zvksc",
+            "zvksed",
+            "zvksg",
+            "zvksh",
+            "zvkt",
+        ];
+        check_features("riscv32", &rust_features, ZIG_RISCV_FEATURES);
+        check_features("riscv64", &rust_features, ZIG_RISCV_FEATURES);
+    }
+
+    #[test]
+    fn wasm_features() {
+        let rust_features = [
+            "atomics",
+            "bulk-memory",
+            "crt-static",
+            "exception-handling",
+            "extended-const",
+            "multimemory",
+            "multivalue",
+            "mutable-globals",
+            "nontrapping-fptoint",
+            "reference-types",
+            "relaxed-simd",
+            "sign-ext",
+            "simd128",
+            "tail-call",
+            "wide-arithmetic",
+        ];
+        check_features("wasm32", &rust_features, ZIG_WASM_FEATURES);
+        check_features("wasm64", &rust_features, ZIG_WASM_FEATURES);
+        assert_eq!(translate_arch_feature("wasm32", "crt-static"), None);
+    }
+
+    #[test]
+    fn powerpc_features() {
+        let rust_features = [
+            "altivec",
+            "msync",
+            "partword-atomics",
+            "power10-vector",
+            "power8-altivec",
+            "power8-crypto",
+            "power8-vector",
+            "power9-altivec",
+            "power9-vector",
+            "quadword-atomics",
+            "vsx",
+        ];
+        check_features("powerpc", &rust_features, ZIG_POWERPC_FEATURES);
+        check_features("powerpc64le", &rust_features, ZIG_POWERPC_FEATURES);
+    }
+
+    #[test]
+    fn loongarch_features() {
+        let rust_features = [
+            "d",
+            "div32",
+            "f",
+            "frecipe",
+            "lam-bh",
+            "lamcas",
+            "lasx",
+            "lbt",
+            "ld-seq-sa",
+            "lsx",
+            "lvz",
+            "relax",
+            "scq",
+            "ual",
+        ];
+        check_features("loongarch64", &rust_features, ZIG_LOONGARCH_FEATURES);
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+/// Severity of a compiler diagnostic.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Severity {
+    /// An error, which fails the build.
+    Error,
+    /// A warning, which does not fail the build.
+    Warning,
+    /// A note, usually attached to another diagnostic.
+    Note,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// A diagnostic reported by zig, e.g. a compile error.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Diagnostic {
+    /// Severity of the diagnostic.
+    pub severity: Severity,
+    /// Message of the diagnostic.
+    pub message: String,
+    /// Source file the diagnostic refers to, if any.
+    pub file: Option<PathBuf>,
+    /// Line of the diagnostic, starting at 1.
+    pub line: Option<u32>,
+    /// Column of the diagnostic, starting at 1.
+    pub column: Option<u32>,
+    /// Notes attached to the diagnostic.
+    pub notes: Vec<Diagnostic>,
+    /// Reference trace of the diagnostic, e.g. `main: src/main.zig:10:5`.
+    pub reference_trace: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Formats the location and message of the diagnostic on a single line.
+    fn fmt_line(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, "{}:{}:", line, column)?;
+            }
+            f.write_str(" ")?;
+        }
+        write!(f, "{}: {}", self.severity.name(), self.message)
+    }
+}
+
+impl Display for Diagnostic {
+    /// Formats the diagnostic compactly, with its notes and the closest reference.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_line(f)?;
+        for note in &self.notes {
+            f.write_str("\n    ")?;
+            note.fmt_line(f)?;
+        }
+        if let Some(reference) = self.reference_trace.first() {
+            write!(f, "\n    referenced by: {}", reference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the diagnostics in the standard error of `zig build`.
+///
+/// Notes are attached to the preceding diagnostic, and duplicates, e.g. of a module compiled by
+/// multiple steps, are removed.
+pub(crate) fn parse_diagnostics(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    let mut reference_trace = false;
+    for line in output.lines() {
+        if reference_trace {
+            if line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                let reference = line.trim();
+                if let (Some(diagnostic), false) =
+                    (diagnostics.last_mut(), reference.contains("hidden"))
+                {
+                    diagnostic.reference_trace.push(reference.to_string());
+                }
+                continue;
+            }
+            reference_trace = false;
+        }
+        if line.trim_end() == "referenced by:" {
+            reference_trace = true;
+            continue;
+        }
+
+        let Some(diagnostic) = parse_line(line) else {
+            continue;
+        };
+        match (diagnostic.severity, diagnostics.last_mut()) {
+            (Severity::Note, Some(parent)) => parent.notes.push(diagnostic),
+            (Severity::Note, None) => {}
+            _ => diagnostics.push(diagnostic),
+        }
+    }
+
+    let mut unique: Vec<Diagnostic> = vec![];
+    for diagnostic in diagnostics {
+        let duplicate = unique.iter().any(|d| {
+            (d.severity, &d.message, &d.file, d.line, d.column)
+                == (
+                    diagnostic.severity,
+                    &diagnostic.message,
+                    &diagnostic.file,
+                    diagnostic.line,
+                    diagnostic.column,
+                )
+        });
+        if !duplicate {
+            unique.push(diagnostic);
+        }
+    }
+    unique
+}
+
+/// Parses a diagnostic line, i.e. `file:line:column: severity: message` or `severity: message`.
+fn parse_line(line: &str) -> Option<Diagnostic> {
+    let severities = [Severity::Error, Severity::Warning, Severity::Note];
+    let (location, severity, message) = severities.into_iter().find_map(|severity| {
+        let marker = format!("{}: ", severity.name());
+        if let Some(message) = line.strip_prefix(&marker) {
+            return Some(("", severity, message));
+        }
+        let (location, message) = line.split_once(&format!(": {}", marker))?;
+        Some((location, severity, message))
+    })?;
+
+    // The build runner reports the failed commands as errors, which are not diagnostics.
+    if location.is_empty() && message.starts_with("the following ") {
+        return None;
+    }
+
+    let mut diagnostic = Diagnostic {
+        severity,
+        message: message.trim().to_string(),
+        file: None,
+        line: None,
+        column: None,
+        notes: vec![],
+        reference_trace: vec![],
+    };
+    if !location.is_empty() {
+        // Split from the end, as the path may contain colons, e.g. a Windows drive.
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next().and_then(|c| c.parse().ok());
+        let line = parts.next().and_then(|l| l.parse().ok());
+        match (parts.next(), line, column) {
+            (Some(file), Some(line), Some(column)) => {
+                diagnostic.file = Some(PathBuf::from(file));
+                diagnostic.line = Some(line);
+                diagnostic.column = Some(column);
+            }
+            // Source lines may contain the severity markers as well.
+            _ if location.contains(char::is_whitespace) => return None,
+            _ => diagnostic.file = Some(PathBuf::from(location)),
+        }
+    }
+    Some(diagnostic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_compile_errors() {
+        let output = "\
+install
+└─ install foo
+   └─ zig build-lib foo Debug native 1 errors
+src/foo.zig:3:5: error: use of undeclared identifier 'bar'
+    bar();
+    ^~~
+src/foo.zig:1:1: note: struct declared here
+referenced by:
+    foo: src/foo.zig:10:5
+    remaining reference traces hidden; use '-freference-trace' to see all reference traces
+C:\\zig\\src\\foo.c:7:2: warning: unused variable 'x' [-Wunused-variable]
+src/foo.zig:3:5: error: use of undeclared identifier 'bar'
+    bar();
+    ^~~
+error: the following command failed with 1 compilation errors:
+error: ld.lld: undefined symbol: baz
+";
+        let diagnostics = parse_diagnostics(output);
+        assert_eq!(diagnostics.len(), 3);
+
+        let error = &diagnostics[0];
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.file, Some(PathBuf::from("src/foo.zig")));
+        assert_eq!((error.line, error.column), (Some(3), Some(5)));
+        assert_eq!(error.notes.len(), 1);
+        assert_eq!(error.reference_trace, ["foo: src/foo.zig:10:5"]);
+        assert_eq!(
+            error.to_string(),
+            "src/foo.zig:3:5: error: use of undeclared identifier 'bar'\n    \
+             src/foo.zig:1:1: note: struct declared here\n    \
+             referenced by: foo: src/foo.zig:10:5"
+        );
+
+        let warning = &diagnostics[1];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.file, Some(PathBuf::from("C:\\zig\\src\\foo.c")));
+
+        assert_eq!(diagnostics[2].message, "ld.lld: undefined symbol: baz");
+        assert_eq!(diagnostics[2].file, None);
+    }
+}
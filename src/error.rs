@@ -0,0 +1,89 @@
+use crate::{Diagnostic, Severity};
+use std::{error, fmt, result};
+
+/// A specialized [`Result`](result::Result) type for the fallible operations of this crate.
+pub type Result<T, E = Error> = result::Result<T, E>;
+
+/// Errors reported by the fallible operations of this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The name of the build option is reserved for a dedicated method of [`Build`].
+    ///
+    /// [`Build`]: crate::Build
+    ReservedOption(String),
+    /// The name or value of the build option can not be passed to `zig build`.
+    InvalidOption(String),
+    /// The configuration of the build, or the environment it is derived from, is invalid.
+    Config(String),
+    /// A file of the build could not be written.
+    Io(String),
+    /// The `zig` command could not be executed.
+    Command(String),
+    /// The `zig build` command failed, with the diagnostics reported by zig.
+    Build {
+        /// Exit status of the command.
+        status: String,
+        /// Deduplicated diagnostics of the build.
+        diagnostics: Vec<Diagnostic>,
+        /// Last lines of the output of the build.
+        output: Vec<String>,
+        /// Whether zig was killed by `SIGKILL`, likely because it ran out of memory.
+        killed: bool,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ReservedOption(name) => {
+                write!(
+                    f,
+                    "the option `{}` is reserved, use its dedicated method",
+                    name
+                )
+            }
+            Error::InvalidOption(message) => write!(f, "invalid option: {}", message),
+            Error::Config(message) => write!(f, "invalid configuration: {}", message),
+            Error::Io(message) => write!(f, "{}", message),
+            Error::Command(message) => write!(f, "failed to execute command: {}", message),
+            Error::Build {
+                status,
+                diagnostics,
+                output,
+                killed,
+            } => {
+                let errors = diagnostics
+                    .iter()
+                    .filter(|d| d.severity == Severity::Error)
+                    .count();
+                let plural = if errors == 1 { "" } else { "s" };
+                write!(
+                    f,
+                    "zig build failed with {} error{}, got: {}",
+                    errors, plural, status
+                )?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                if *killed {
+                    write!(
+                        f,
+                        "\nzig was killed by SIGKILL, probably by the out-of-memory killer; \
+                         consider lowering the memory limit with `Build::maxrss`, or the number \
+                         of jobs"
+                    )?;
+                }
+                if !output.is_empty() {
+                    write!(f, "\n\nlast {} lines of output:", output.len())?;
+                    for line in output {
+                        write!(f, "\n{}", line)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
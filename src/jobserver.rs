@@ -0,0 +1,295 @@
+//! Client of the GNU make jobserver shared by Cargo with the build scripts.
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// How a jobserver is reached, as advertised in `MAKEFLAGS`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum JobserverAuth {
+    /// A named pipe, i.e. `--jobserver-auth=fifo:PATH`.
+    Fifo(PathBuf),
+    /// Inherited file descriptors, i.e. `--jobserver-auth=R,W`.
+    Fds(i32, i32),
+}
+
+/// Parses the jobserver of `flags`, e.g. the value of `CARGO_MAKEFLAGS`.
+///
+/// Only the last `--jobserver-auth` or `--jobserver-fds` argument is considered, as make does.
+pub(crate) fn parse_makeflags(flags: &str) -> Option<JobserverAuth> {
+    let auth = flags.split_whitespace().rev().find_map(|arg| {
+        arg.strip_prefix("--jobserver-auth=")
+            .or_else(|| arg.strip_prefix("--jobserver-fds="))
+    })?;
+    if let Some(path) = auth.strip_prefix("fifo:") {
+        return Some(JobserverAuth::Fifo(PathBuf::from(path)));
+    }
+    let (read, write) = auth.split_once(',')?;
+    Some(JobserverAuth::Fds(read.parse().ok()?, write.parse().ok()?))
+}
+
+/// A connection to a jobserver, holding the tokens it acquired until it is dropped.
+pub(crate) struct Jobserver {
+    reader: File,
+    writer: File,
+    tokens: Vec<u8>,
+}
+
+impl Jobserver {
+    /// Connects to the jobserver reached through `auth`.
+    ///
+    /// Tokens are read without blocking, so that tokens taken by another process in the meantime
+    /// are not waited for. This requires a file description of the pipe of its own, which is only
+    /// possible for named pipes, and for inherited descriptors on Linux. Changing the flags of
+    /// the description shared with Cargo would break the blocking reads of the other clients.
+    pub(crate) fn connect(auth: &JobserverAuth) -> io::Result<Self> {
+        let (reader, writer) = match auth {
+            JobserverAuth::Fifo(path) => {
+                // The read end is opened first, so that opening the write end does not block.
+                let reader = sys::open_nonblocking(path)?;
+                (reader, File::options().write(true).open(path)?)
+            }
+            JobserverAuth::Fds(read, write) => (sys::reader(*read)?, sys::duplicate(*write)?),
+        };
+        Ok(Jobserver {
+            reader,
+            writer,
+            tokens: vec![],
+        })
+    }
+
+    /// Acquires up to `max` tokens, taking the tokens available without waiting for the tokens
+    /// held by other processes.
+    ///
+    /// If no token is available, waits up to `wait` for the first one, so that the build is not
+    /// limited to the implicit token whenever all tokens are briefly in use. Returns the number
+    /// of tokens acquired, which excludes the implicit token of the process.
+    pub(crate) fn acquire(&mut self, max: usize, wait: Duration) -> io::Result<usize> {
+        let start = Instant::now();
+        self.take_available(max)?;
+        while self.tokens.is_empty() && max > 0 {
+            let Some(remaining) = wait.checked_sub(start.elapsed()) else {
+                break;
+            };
+            if !sys::wait_readable(&self.reader, remaining)? {
+                break;
+            }
+            // Another process may have taken the token first, in which case this waits again.
+            self.take_available(max)?;
+        }
+        Ok(self.tokens())
+    }
+
+    /// Takes the available tokens, up to `max` tokens in total.
+    fn take_available(&mut self, max: usize) -> io::Result<()> {
+        while self.tokens.len() < max {
+            let mut token = [0u8];
+            match self.reader.read(&mut token) {
+                Ok(1) => self.tokens.push(token[0]),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                // No token is available, e.g. another process took the last one.
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of tokens acquired.
+    pub(crate) fn tokens(&self) -> usize {
+        self.tokens.len()
+    }
+}
+
+impl Drop for Jobserver {
+    /// Releases the acquired tokens.
+    fn drop(&mut self) {
+        if let Err(e) = self.writer.write_all(&self.tokens) {
+            eprintln!("Warning: failed to release the jobserver tokens: {}", e);
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::{
+        fs::File,
+        io,
+        os::{
+            raw::{c_int, c_short},
+            unix::{
+                fs::OpenOptionsExt,
+                io::{AsRawFd, FromRawFd},
+            },
+        },
+        path::Path,
+        time::Duration,
+    };
+
+    const F_SETFD: c_int = 2;
+    const FD_CLOEXEC: c_int = 1;
+    const POLLIN: c_short = 1;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const O_NONBLOCK: c_int = 0o4000;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const O_NONBLOCK: c_int = 4;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    type NfdsT = std::os::raw::c_ulong;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    type NfdsT = std::os::raw::c_uint;
+
+    #[repr(C)]
+    struct PollFd {
+        fd: c_int,
+        events: c_short,
+        revents: c_short,
+    }
+
+    extern "C" {
+        fn dup(fd: c_int) -> c_int;
+        fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+        fn poll(fds: *mut PollFd, nfds: NfdsT, timeout: c_int) -> c_int;
+    }
+
+    /// Opens the named pipe at `path` for reading without blocking.
+    pub(super) fn open_nonblocking(path: &Path) -> io::Result<File> {
+        File::options()
+            .read(true)
+            .custom_flags(O_NONBLOCK)
+            .open(path)
+    }
+
+    /// Opens the inherited read end `fd` of a pipe for reading without blocking, by reopening
+    /// the pipe through `/proc`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(super) fn reader(fd: c_int) -> io::Result<File> {
+        open_nonblocking(Path::new(&format!("/proc/self/fd/{}", fd)))
+    }
+
+    /// Inherited descriptors can not be reopened outside of Linux.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub(super) fn reader(_fd: c_int) -> io::Result<File> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "inherited jobserver descriptors are only supported on Linux",
+        ))
+    }
+
+    /// Waits up to `timeout` for `file` to be readable, returning whether it is.
+    pub(super) fn wait_readable(file: &File, timeout: Duration) -> io::Result<bool> {
+        let mut fd = PollFd {
+            fd: file.as_raw_fd(),
+            events: POLLIN,
+            revents: 0,
+        };
+        let timeout = c_int::try_from(timeout.as_millis()).unwrap_or(c_int::MAX);
+        // SAFETY: `fd` is a single valid `pollfd`.
+        match unsafe { poll(&mut fd, 1, timeout) } {
+            -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => Ok(true),
+            -1 => Err(io::Error::last_os_error()),
+            ready => Ok(ready > 0 && fd.revents & POLLIN != 0),
+        }
+    }
+
+    /// Duplicates the inherited descriptor `fd`, which is not inherited by child processes.
+    pub(super) fn duplicate(fd: c_int) -> io::Result<File> {
+        // SAFETY: `dup` fails if `fd` is not an open descriptor.
+        let new = unsafe { dup(fd) };
+        if new < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `new` was just created and is owned by nothing else.
+        let file = unsafe { File::from_raw_fd(new) };
+        // SAFETY: `new` is a valid descriptor.
+        if unsafe { fcntl(new, F_SETFD, FD_CLOEXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(file)
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::{fs::File, io, path::Path, time::Duration};
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the jobserver is only supported on Unix platforms",
+        )
+    }
+
+    /// Named pipes are only supported on Unix platforms.
+    pub(super) fn open_nonblocking(_path: &Path) -> io::Result<File> {
+        Err(unsupported())
+    }
+
+    /// Inherited descriptors are only supported on Unix platforms.
+    pub(super) fn reader(_fd: i32) -> io::Result<File> {
+        Err(unsupported())
+    }
+
+    /// Inherited descriptors are only supported on Unix platforms.
+    pub(super) fn duplicate(_fd: i32) -> io::Result<File> {
+        Err(unsupported())
+    }
+
+    pub(super) fn wait_readable(_file: &File, _timeout: Duration) -> io::Result<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_jobserver_auth() {
+        assert_eq!(
+            parse_makeflags("-j --jobserver-fds=3,4 --jobserver-auth=5,6"),
+            Some(JobserverAuth::Fds(5, 6))
+        );
+        assert_eq!(
+            parse_makeflags(" -j4 --jobserver-auth=fifo:/tmp/GMfifo123"),
+            Some(JobserverAuth::Fifo(PathBuf::from("/tmp/GMfifo123")))
+        );
+        assert_eq!(parse_makeflags("-j --jobserver-auth=sem"), None);
+        assert_eq!(parse_makeflags("-j4"), None);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn acquire_available_tokens() {
+        use std::{os::unix::io::AsRawFd, thread};
+
+        let (mut reader, mut writer) = crate::progress::progress_pipe().unwrap();
+        writer.write_all(b"++").unwrap();
+        let auth = JobserverAuth::Fds(reader.as_raw_fd(), writer.as_raw_fd());
+        let mut jobserver = Jobserver::connect(&auth).unwrap();
+        assert_eq!(jobserver.acquire(1, Duration::ZERO).unwrap(), 1);
+        // Only one more token is available, which must not wait for more.
+        let start = Instant::now();
+        assert_eq!(jobserver.acquire(4, Duration::from_secs(10)).unwrap(), 2);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        drop(jobserver);
+
+        let mut tokens = [0u8; 2];
+        reader.read_exact(&mut tokens).unwrap();
+        assert_eq!(&tokens, b"++");
+
+        // Without any token, the first one released in time is waited for.
+        let mut jobserver = Jobserver::connect(&auth).unwrap();
+        assert_eq!(jobserver.acquire(4, Duration::from_millis(10)).unwrap(), 0);
+        let release = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            writer.write_all(b"+").unwrap();
+        });
+        assert_eq!(jobserver.acquire(4, Duration::from_secs(10)).unwrap(), 1);
+        release.join().unwrap();
+    }
+}
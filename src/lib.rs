@@ -42,5 +42,32 @@
 //! ```
 
 mod build;
+mod cargo_config;
+mod codegen;
+mod cpu;
+mod diagnostic;
+mod error;
+mod jobserver;
+mod json;
+mod manifest;
+mod memory;
+mod options;
+mod output;
+mod progress;
+mod step;
+mod summary;
+mod target;
+mod target_spec;
+mod toml;
 
 pub use build::*;
+pub use codegen::*;
+pub use cpu::*;
+pub use diagnostic::*;
+pub use error::*;
+pub use options::*;
+pub use output::*;
+pub use progress::*;
+pub use step::*;
+pub use summary::*;
+pub use target::*;
@@ -0,0 +1,358 @@
+//! Support for the `[package.metadata.zigcli]` table of the Cargo manifest.
+
+use crate::{toml::Value, Build, Cpu, Optimize, ProfileMode, ReleaseMode, Target};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Reads the `[package.metadata.zigcli]` table of the manifest of the package being built.
+///
+/// Returns `None` if the manifest has no such table, or can not be read.
+pub(crate) fn read_metadata() -> Option<Value> {
+    let path = manifest_dir()?.join("Cargo.toml");
+    let contents = fs::read_to_string(&path).ok()?;
+    match Value::parse(&contents) {
+        Ok(manifest) => manifest.get_path("package.metadata.zigcli").cloned(),
+        Err(e) => {
+            eprintln!(
+                "Warning: failed to parse {}: {}; ignoring zigcli metadata.",
+                path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Returns the directory of the manifest of the package being built.
+pub(crate) fn manifest_dir() -> Option<PathBuf> {
+    env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from)
+}
+
+/// Returns the names of the packages declared in the `packages` table of `metadata`.
+pub(crate) fn package_names(metadata: &Value) -> Vec<String> {
+    match metadata.get("packages") {
+        Some(Value::Table(packages)) => packages.keys().cloned().collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns the configuration of the package `name`, or of the top-level table if `None`.
+///
+/// The keys of the top-level table are shared by all packages, and can be overridden in the
+/// table of each package.
+pub(crate) fn package_config(
+    metadata: &Value,
+    name: Option<&str>,
+) -> Result<BTreeMap<String, Value>, String> {
+    let Value::Table(table) = metadata else {
+        return Err("expected a table".to_string());
+    };
+    let mut config = table.clone();
+    let packages = config.remove("packages");
+    if let Some(name) = name {
+        match packages.as_ref().and_then(|p| p.get(name)) {
+            Some(Value::Table(package)) => config.extend(package.clone()),
+            Some(_) => return Err(format!("package `{}` must be a table", name)),
+            None => return Err(format!("unknown package `{}`", name)),
+        }
+    }
+    if !config.contains_key("path") {
+        return match packages {
+            Some(_) if name.is_none() => {
+                Err("missing `path` key; the manifest declares multiple packages".to_string())
+            }
+            _ => Err("missing `path` key".to_string()),
+        };
+    }
+    Ok(config)
+}
+
+/// Applies the package configuration `config` to `build`.
+pub(crate) fn configure(build: &mut Build, config: &BTreeMap<String, Value>) -> Result<(), String> {
+    for (key, value) in config {
+        let result = match &key[..] {
+            "path" => Ok(()),
+            "step" => string(value).map(|step| {
+                build.step(step);
+            }),
+            "steps" => array(value).and_then(|steps| {
+                let steps = steps.iter().map(string).collect::<Result<Vec<_>, _>>()?;
+                build.steps(steps);
+                Ok(())
+            }),
+            "release" => string(value).and_then(release_value).map(|release| {
+                build.release(release);
+            }),
+            "optimize" => string(value).and_then(optimize_value).map(|optimize| {
+                build.optimize(optimize);
+            }),
+            "codegen-optimize" => boolean(value).map(|enabled| {
+                build.codegen_optimize(enabled);
+            }),
+            "target" => string(value).map(|target| {
+                build.target(target);
+            }),
+            "targets" => table(value).and_then(|targets| {
+                for (rust, zig) in targets {
+                    build.target_mapping(rust, string(zig).and_then(target_value)?);
+                }
+                Ok(())
+            }),
+            "cpu" => string(value).and_then(|cpu| {
+                cpu.parse::<Cpu>().map_err(|e| e.to_string())?;
+                build.cpu(cpu);
+                Ok(())
+            }),
+            "jobs" => integer(value).map(|jobs| {
+                build.jobs(jobs);
+            }),
+            "options" => options(value).and_then(|options| {
+                for (name, values) in options {
+                    build
+                        .define_list(&name, values)
+                        .map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }),
+            "profiles" => table(value).and_then(|profiles| {
+                for (profile, mode) in profiles {
+                    let mode =
+                        parse_profile_mode(mode).map_err(|e| format!("{}: {}", profile, e))?;
+                    if let Some(release) = mode.release {
+                        build.profile_release(profile, release);
+                    }
+                    if let Some(optimize) = mode.optimize {
+                        build.profile_optimize(profile, optimize);
+                    }
+                }
+                Ok(())
+            }),
+            "link" => array(value).and_then(|libs| {
+                for lib in libs {
+                    build.link_lib(string(lib)?);
+                }
+                Ok(())
+            }),
+            "rerun-if-changed" => rerun_paths(value, config).map(|paths| {
+                for path in paths {
+                    build.rerun_if_changed(path);
+                }
+            }),
+            _ => Err("unknown key".to_string()),
+        };
+        result.map_err(|e| format!("`{}`: {}", key, e))?;
+    }
+    Ok(())
+}
+
+/// Returns the mode configured for `profile` in the `profiles` table of `metadata`.
+pub(crate) fn profile_mode(metadata: &Value, profile: &str) -> Result<Option<ProfileMode>, String> {
+    match metadata.get("profiles").and_then(|p| p.get(profile)) {
+        Some(value) => parse_profile_mode(value).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Parses the mode of a profile.
+///
+/// A profile is either mapped to the name of an optimization modus, e.g. `"ReleaseSmall"`, or to
+/// a table with the `release` and `optimize` keys.
+fn parse_profile_mode(value: &Value) -> Result<ProfileMode, String> {
+    match value {
+        Value::String(optimize) => Ok(ProfileMode {
+            release: None,
+            optimize: Some(optimize_value(optimize)?),
+        }),
+        Value::Table(table) => {
+            if let Some(key) = table
+                .keys()
+                .find(|k| !matches!(&k[..], "release" | "optimize"))
+            {
+                return Err(format!("unknown key `{}`", key));
+            }
+            let get = |key: &str| table.get(key).map(string).transpose();
+            Ok(ProfileMode {
+                release: get("release")?.map(release_value).transpose()?,
+                optimize: get("optimize")?.map(optimize_value).transpose()?,
+            })
+        }
+        _ => Err("expected a string or a table".to_string()),
+    }
+}
+
+/// Translates the `options` value, either an array of `-D` options or a table of option values,
+/// to the values of each option.
+///
+/// Options given several times in the array are list options, e.g. `["-Dx=a", "-Dx=b"]`, and a
+/// `-D` option without value is a boolean option set to `true`.
+fn options(value: &Value) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut options = Vec::<(String, Vec<String>)>::new();
+    match value {
+        Value::Array(values) => {
+            for option in values {
+                let option = string(option)?;
+                let Some(definition) = option.strip_prefix("-D") else {
+                    return Err(format!("`{}` is not a `-D` option", option));
+                };
+                let (name, value) = definition.split_once('=').unwrap_or((definition, "true"));
+                match options.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, values)) => values.push(value.to_string()),
+                    None => options.push((name.to_string(), vec![value.to_string()])),
+                }
+            }
+        }
+        Value::Table(values) => {
+            for (name, value) in values {
+                let value = match value {
+                    Value::String(s) => s.clone(),
+                    Value::Integer(i) => i.to_string(),
+                    Value::Float(f) => f.to_string(),
+                    Value::Boolean(b) => b.to_string(),
+                    _ => return Err(format!("option `{}` must be a scalar", name)),
+                };
+                options.push((name.clone(), vec![value]));
+            }
+        }
+        _ => return Err("expected an array or a table".to_string()),
+    }
+    Ok(options)
+}
+
+/// Translates the rerun policy, either `true` for the package directory or an array of paths.
+///
+/// Paths are relative to the directory of the manifest.
+fn rerun_paths(value: &Value, config: &BTreeMap<String, Value>) -> Result<Vec<PathBuf>, String> {
+    let dir = manifest_dir().unwrap_or_default();
+    match value {
+        Value::Boolean(false) => Ok(vec![]),
+        Value::Boolean(true) => {
+            let path = config.get("path").map(string).transpose()?.unwrap_or(".");
+            Ok(vec![dir.join(path)])
+        }
+        Value::Array(paths) => paths
+            .iter()
+            .map(|path| string(path).map(|path| dir.join(Path::new(path))))
+            .collect(),
+        _ => Err("expected a boolean or an array".to_string()),
+    }
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    value
+        .as_str()
+        .ok_or_else(|| "expected a string".to_string())
+}
+
+fn boolean(value: &Value) -> Result<bool, String> {
+    match value {
+        Value::Boolean(b) => Ok(*b),
+        _ => Err("expected a boolean".to_string()),
+    }
+}
+
+fn integer(value: &Value) -> Result<usize, String> {
+    match value {
+        Value::Integer(i) => {
+            usize::try_from(*i).map_err(|_| "expected a positive integer".to_string())
+        }
+        _ => Err("expected an integer".to_string()),
+    }
+}
+
+fn array(value: &Value) -> Result<&[Value], String> {
+    match value {
+        Value::Array(values) => Ok(values),
+        _ => Err("expected an array".to_string()),
+    }
+}
+
+fn table(value: &Value) -> Result<&BTreeMap<String, Value>, String> {
+    match value {
+        Value::Table(table) => Ok(table),
+        _ => Err("expected a table".to_string()),
+    }
+}
+
+fn release_value(name: &str) -> Result<ReleaseMode, String> {
+    ReleaseMode::from_name(name).ok_or_else(|| format!("unknown release mode `{}`", name))
+}
+
+fn optimize_value(name: &str) -> Result<Optimize, String> {
+    Optimize::from_name(name).ok_or_else(|| format!("unknown optimization modus `{}`", name))
+}
+
+fn target_value(target: &str) -> Result<Target, String> {
+    target.parse::<Target>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_package_config() {
+        let metadata = Value::parse(
+            r#"
+            release = "fast"
+            options = { shared = false, level = 2 }
+
+            [packages.foo]
+            path = "libfoo"
+
+            [packages.bar]
+            path = "libbar"
+            release = "small"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(package_names(&metadata), ["bar", "foo"]);
+        assert!(package_config(&metadata, None).is_err());
+        assert!(package_config(&metadata, Some("baz")).is_err());
+
+        let foo = package_config(&metadata, Some("foo")).unwrap();
+        assert_eq!(foo["release"].as_str(), Some("fast"));
+        assert_eq!(
+            options(&foo["options"]).unwrap(),
+            [
+                ("level".to_string(), vec!["2".to_string()]),
+                ("shared".to_string(), vec!["false".to_string()])
+            ]
+        );
+        let bar = package_config(&metadata, Some("bar")).unwrap();
+        assert_eq!(bar["path"].as_str(), Some("libbar"));
+        assert_eq!(bar["release"].as_str(), Some("small"));
+    }
+
+    #[test]
+    fn reject_invalid_options() {
+        let metadata =
+            Value::parse(r#"options = ["-Dfeature=a", "-Dfeature=b", "-Dstrip"]"#).unwrap();
+        assert_eq!(
+            options(metadata.get("options").unwrap()).unwrap(),
+            [
+                (
+                    "feature".to_string(),
+                    vec!["a".to_string(), "b".to_string()]
+                ),
+                ("strip".to_string(), vec!["true".to_string()])
+            ]
+        );
+
+        let mut build = Build::new("zig-project");
+        for options in [
+            r#"["strip"]"#,
+            r#"["-Dtarget=x86_64-linux"]"#,
+            r#"{ "a b" = 1 }"#,
+        ] {
+            let config = Value::parse(&format!("options = {}", options)).unwrap();
+            let Value::Table(config) = config else {
+                unreachable!()
+            };
+            let e = configure(&mut build, &config).unwrap_err();
+            assert!(e.starts_with("`options`: "), "{}", e);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Detection of the memory available to the build, from the cgroup limits.
+
+use std::{fs, path::Path};
+
+/// Limits of cgroup v1 at or above this value, i.e. the page-aligned `i64::MAX`, are unlimited.
+const CGROUP_V1_UNLIMITED: u64 = 0x7fff_ffff_ffff_f000;
+
+/// Returns the memory available to the current process, in bytes.
+///
+/// This is the lowest of the memory limits of its cgroups, v1 or v2, or `None` if none of them
+/// is limited, in which case zig already defaults to the total memory.
+pub(crate) fn memory_limit() -> Option<u64> {
+    memory_limit_in(Path::new("/"))
+}
+
+/// Returns the memory available to the current process, with the files of `/proc` and `/sys`
+/// read relative to `root`.
+fn memory_limit_in(root: &Path) -> Option<u64> {
+    let read = |path: &str| fs::read_to_string(root.join(path)).ok();
+    let mut limits = vec![];
+    if let Some(cgroups) = read("proc/self/cgroup") {
+        for line in cgroups.lines() {
+            let mut fields = line.splitn(3, ':');
+            let (Some(_), Some(controllers), Some(path)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let (dir, file) = if controllers.is_empty() {
+                ("sys/fs/cgroup", "memory.max")
+            } else if controllers.split(',').any(|c| c == "memory") {
+                ("sys/fs/cgroup/memory", "memory.limit_in_bytes")
+            } else {
+                continue;
+            };
+            // Limits of the parent cgroups apply as well. The path is relative to the root of
+            // the cgroup namespace, which is the mounted hierarchy within containers.
+            let mut cgroup = Some(Path::new(path.trim_start_matches('/')));
+            while let Some(relative) = cgroup {
+                let limit = read_limit(&root.join(dir).join(relative).join(file));
+                limits.extend(limit.filter(|&limit| limit < CGROUP_V1_UNLIMITED));
+                cgroup = relative.parent();
+            }
+        }
+    }
+    limits.into_iter().min()
+}
+
+/// Reads a cgroup memory limit, where `max` means unlimited.
+fn read_limit(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_cgroup_limits() {
+        let root = std::env::temp_dir().join(format!("zigcli-memory-{}", std::process::id()));
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write(
+            "proc/meminfo",
+            "MemTotal:       16318412 kB\nMemFree: 1 kB\n",
+        );
+        assert_eq!(memory_limit_in(&root), None);
+
+        write("proc/self/cgroup", "0::/ci/job\n");
+        write("sys/fs/cgroup/ci/job/memory.max", "max\n");
+        assert_eq!(memory_limit_in(&root), None);
+        write("sys/fs/cgroup/ci/memory.max", "4294967296\n");
+        assert_eq!(memory_limit_in(&root), Some(4 << 30));
+
+        write("proc/self/cgroup", "5:cpu,cpuacct:/\n4:memory:/\n");
+        write(
+            "sys/fs/cgroup/memory/memory.limit_in_bytes",
+            "9223372036854771712\n",
+        );
+        assert_eq!(memory_limit_in(&root), None);
+        write("sys/fs/cgroup/memory/memory.limit_in_bytes", "2147483648\n");
+        assert_eq!(memory_limit_in(&root), Some(2 << 30));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
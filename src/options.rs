@@ -0,0 +1,253 @@
+use std::fmt::{self, Display};
+
+/// Type of a build option declared by a zig package.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum OptionType {
+    /// A boolean, see [`Build::define_bool`](crate::Build::define_bool).
+    Bool,
+    /// An integer, see [`Build::define_int`](crate::Build::define_int).
+    Int,
+    /// A floating-point number.
+    Float,
+    /// A tag of an enum, see [`Build::define_enum`](crate::Build::define_enum).
+    Enum,
+    /// A list of tags of an enum.
+    EnumList,
+    /// A string, see [`Build::define_str`](crate::Build::define_str).
+    String,
+    /// A list of strings, see [`Build::define_list`](crate::Build::define_list).
+    List,
+    /// A build ID style, e.g. `fast`, `sha1` or `0x1234`.
+    BuildId,
+    /// A path, see [`Build::define_path`](crate::Build::define_path).
+    LazyPath,
+    /// A list of paths.
+    LazyPathList,
+    /// A type not known to this crate.
+    Unknown(String),
+}
+
+impl OptionType {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "bool" => OptionType::Bool,
+            "int" => OptionType::Int,
+            "float" => OptionType::Float,
+            "enum" => OptionType::Enum,
+            "enum_list" => OptionType::EnumList,
+            "string" => OptionType::String,
+            "list" => OptionType::List,
+            "build_id" => OptionType::BuildId,
+            "lazy_path" => OptionType::LazyPath,
+            "lazy_path_list" => OptionType::LazyPathList,
+            unknown => OptionType::Unknown(unknown.to_string()),
+        }
+    }
+}
+
+impl Display for OptionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OptionType::Bool => "bool",
+            OptionType::Int => "int",
+            OptionType::Float => "float",
+            OptionType::Enum => "enum",
+            OptionType::EnumList => "enum_list",
+            OptionType::String => "string",
+            OptionType::List => "list",
+            OptionType::BuildId => "build_id",
+            OptionType::LazyPath => "lazy_path",
+            OptionType::LazyPathList => "lazy_path_list",
+            OptionType::Unknown(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+/// A build option declared by a zig package, as listed by `zig build -h`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ProjectOption {
+    /// Name of the option, without the `-D` prefix.
+    pub name: String,
+    /// Type of the option.
+    pub kind: OptionType,
+    /// Description of the option.
+    pub description: String,
+    /// Supported values of enum options.
+    pub values: Vec<String>,
+}
+
+impl ProjectOption {
+    /// Checks whether `value` is a valid value of the option.
+    ///
+    /// A missing value is only valid for boolean options, which it sets to `true`.
+    pub fn accepts(&self, value: Option<&str>) -> bool {
+        match (&self.kind, value) {
+            (OptionType::Bool, None) => true,
+            (_, None) => false,
+            (OptionType::Bool, Some(value)) => matches!(value, "true" | "false"),
+            (OptionType::Int, Some(value)) => parse_zig_int(value).is_some(),
+            (OptionType::Float, Some(value)) => value.parse::<f64>().is_ok(),
+            (OptionType::Enum | OptionType::EnumList, Some(value)) => {
+                self.values.is_empty() || self.values.iter().any(|v| v == value)
+            }
+            (_, Some(_)) => true,
+        }
+    }
+}
+
+/// Parses an integer like zig parses the value of an `int` option, e.g. `-42`, `0x1f` or
+/// `1_000`.
+fn parse_zig_int(value: &str) -> Option<i128> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let prefix = digits.get(..2).map(str::to_ascii_lowercase);
+    let (radix, digits) = match prefix.as_deref() {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, digits),
+    };
+    // Underscores may only separate digits.
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return None;
+    }
+    let digits = digits.replace('_', "");
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let value = i128::from_str_radix(&digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// Parses the `Project-Specific Options` section of the output of `zig build -h`.
+pub(crate) fn parse_project_options(help: &str) -> Vec<ProjectOption> {
+    let mut options: Vec<ProjectOption> = vec![];
+    let mut enum_values = false;
+    let lines = help
+        .lines()
+        .skip_while(|line| !line.starts_with("Project-Specific Options:"))
+        .skip(1)
+        .take_while(|line| line.is_empty() || line.starts_with(' '));
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(option) = trimmed.strip_prefix("-D") {
+            let Some((name, rest)) = option.split_once("=[") else {
+                continue;
+            };
+            let Some((kind, description)) = rest.split_once(']') else {
+                continue;
+            };
+            enum_values = false;
+            options.push(ProjectOption {
+                name: name.to_string(),
+                kind: OptionType::from_name(kind),
+                description: description.trim().to_string(),
+                values: vec![],
+            });
+        } else if trimmed == "Supported Values:" {
+            enum_values = true;
+        } else if let Some(option) = options.last_mut().filter(|_| !trimmed.is_empty()) {
+            if enum_values {
+                option.values.push(trimmed.to_string());
+            } else {
+                option.description.push('\n');
+                option.description.push_str(trimmed);
+            }
+        }
+    }
+    options
+}
+
+/// Returns the name of the option in `options` which is the closest to `name`, if any is close
+/// enough to be a likely misspelling.
+pub(crate) fn closest_option<'a>(name: &str, options: &'a [ProjectOption]) -> Option<&'a str> {
+    let normalize = |name: &str| name.to_ascii_lowercase().replace('-', "_");
+    let name = normalize(name);
+    options
+        .iter()
+        .filter_map(|option| {
+            let candidate = normalize(&option.name);
+            let distance = edit_distance(&name, &candidate);
+            let similar = distance <= (name.len().max(candidate.len()) / 3).max(2)
+                || name.contains(&candidate)
+                || candidate.contains(&name);
+            similar.then_some((distance, &*option.name))
+        })
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELP: &str = "\
+Usage: zig build [steps] [options]
+
+Steps:
+  install (default)            Copy build artifacts to prefix path
+  uninstall                    Remove build artifacts from prefix path
+
+General Options:
+  -p, --prefix [path]          Where to install files (default: zig-out)
+
+Project-Specific Options:
+  -Dtarget=[string]            The CPU architecture, OS, and ABI to build for
+  -Doptimize=[enum]            Prioritize performance, safety, or binary size
+                                 Supported Values:
+                                   Debug
+                                   ReleaseSafe
+                                   ReleaseFast
+                                   ReleaseSmall
+  -Dsimd=[bool]                Enable SIMD
+  -Dlevel=[int]                Compression level
+
+System Integration Options:
+  --search-prefix [path]       Add a path to look for binaries, libraries, headers
+";
+
+    #[test]
+    fn parse_help() {
+        let options = parse_project_options(HELP);
+        let names = options.iter().map(|o| &*o.name).collect::<Vec<_>>();
+        assert_eq!(names, ["target", "optimize", "simd", "level"]);
+        assert_eq!(options[1].kind, OptionType::Enum);
+        assert_eq!(options[1].values.len(), 4);
+        assert_eq!(options[2].description, "Enable SIMD");
+
+        assert!(options[1].accepts(Some("ReleaseFast")));
+        assert!(!options[1].accepts(Some("Fast")));
+        assert!(options[2].accepts(None));
+        assert!(!options[3].accepts(Some("high")));
+        for value in ["-42", "+7", "0x1F", "0o17", "0b101", "1_000", "0XFF"] {
+            assert!(options[3].accepts(Some(value)), "{}", value);
+        }
+        for value in ["", "0x", "1__0", "_1", "1_", "0b2", "--1", "1.5"] {
+            assert!(!options[3].accepts(Some(value)), "{}", value);
+        }
+
+        assert_eq!(closest_option("enable_simd", &options), Some("simd"));
+        assert_eq!(closest_option("levle", &options), Some("level"));
+        assert_eq!(closest_option("unrelated", &options), None);
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+    sync::mpsc,
+    thread,
+};
+
+/// Handling of the output of `zig build`.
+#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum OutputMode {
+    /// Only print the output if the build fails.
+    Quiet,
+    /// Print the output line by line as it is produced, prefixed with the package name.
+    #[default]
+    Streamed,
+    /// Stream the output, and also report every line to Cargo as a warning, so that it is shown
+    /// without `-vv`.
+    Verbose,
+}
+
+/// Output of a finished command.
+pub(crate) struct CommandOutput {
+    pub(crate) status: ExitStatus,
+    /// Standard error, with the ANSI escape sequences removed.
+    pub(crate) stderr: String,
+    /// Lines of the standard output and standard error in the order they were received, with the
+    /// ANSI escape sequences removed.
+    pub(crate) lines: Vec<String>,
+}
+
+/// Runs `cmd` with its standard output and standard error captured through pipes.
+///
+/// Depending on `mode`, the lines are printed to the standard error as they are received,
+/// prefixed with `prefix`. All lines are written to the file at `log`, if any.
+pub(crate) fn run_command(
+    cmd: &mut Command,
+    mode: OutputMode,
+    prefix: &str,
+    log: Option<&Path>,
+) -> io::Result<CommandOutput> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+    let (sender, receiver) = mpsc::channel();
+    let readers = [
+        forward_lines(child.stdout.take().unwrap(), false, sender.clone()),
+        forward_lines(child.stderr.take().unwrap(), true, sender),
+    ];
+
+    let mut log = match log.map(File::create).transpose() {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("Warning: failed to create the build log: {}", e);
+            None
+        }
+    };
+    let mut stderr = String::new();
+    let mut lines = vec![];
+    for (is_stderr, line) in receiver {
+        if mode != OutputMode::Quiet {
+            eprintln!("{} {}", prefix, line);
+        }
+        let line = strip_ansi(&line);
+        if mode == OutputMode::Verbose {
+            println!("cargo:warning={} {}", prefix, line);
+        }
+        if let Some(file) = &mut log {
+            if writeln!(file, "{}", line).is_err() {
+                log = None;
+            }
+        }
+        if is_stderr {
+            stderr.push_str(&line);
+            stderr.push('\n');
+        }
+        lines.push(line);
+    }
+    for reader in readers {
+        let _ = reader.join();
+    }
+    Ok(CommandOutput {
+        status: child.wait()?,
+        stderr,
+        lines,
+    })
+}
+
+/// Checks whether the process of `status` was killed by `SIGKILL`, e.g. by the out-of-memory
+/// killer.
+pub(crate) fn killed_by_sigkill(status: &ExitStatus) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal() == Some(9)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        false
+    }
+}
+
+/// Sends the lines read from `pipe` through `sender`, tagged with `is_stderr`.
+fn forward_lines(
+    pipe: impl Read + Send + 'static,
+    is_stderr: bool,
+    sender: mpsc::Sender<(bool, String)>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = vec![];
+        while let Ok(1..) = reader.read_until(b'\n', &mut line) {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']).to_string();
+            if sender.send((is_stderr, text)).is_err() {
+                break;
+            }
+            line.clear();
+        }
+    })
+}
+
+/// Removes the ANSI escape sequences, i.e. the colors, from `s`.
+pub(crate) fn strip_ansi(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        // Control sequences end with a byte in the range `@` to `~`.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_colors() {
+        assert_eq!(
+            strip_ansi("\x1b[1msrc/foo.zig:3:5: \x1b[31merror: \x1b[0mbar"),
+            "src/foo.zig:3:5: error: bar"
+        );
+    }
+}
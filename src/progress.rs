@@ -0,0 +1,301 @@
+//! Decoding of the progress reported by zig through the `ZIG_PROGRESS` file descriptor.
+//!
+//! The messages follow the layout of `std.Progress` since zig 0.13, which is not versioned.
+//! Messages which do not match it stop the decoding, instead of being misinterpreted.
+
+use crate::OutputMode;
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Read},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+/// Maximum length of the name of a node, in bytes.
+const MAX_NAME_LEN: usize = 40;
+/// Size of a serialized node, i.e. the completed and estimated counts followed by the name.
+const NODE_SIZE: usize = 8 + MAX_NAME_LEN;
+/// Parent of the unused nodes, the parent of the root node being `u8::MAX`.
+const UNUSED_PARENT: u8 = u8::MAX - 1;
+
+/// Callback receiving the progress of a build, see
+/// [`Build::on_progress`](crate::Build::on_progress).
+pub(crate) type ProgressCallback = Box<dyn FnMut(&Progress) + Send>;
+
+/// A node of the progress tree, e.g. a build step or a compilation stage.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ProgressNode {
+    /// Name of the node, e.g. `compile lib foo Debug native`.
+    pub name: String,
+    /// Number of completed items.
+    pub completed: u32,
+    /// Estimated number of items, if known.
+    pub estimated_total: Option<u32>,
+    /// Index of the parent node in [`Progress::nodes`], if any.
+    pub parent: Option<usize>,
+}
+
+/// A snapshot of the progress of `zig build`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Progress {
+    /// Nodes of the progress tree, in the order they were reported.
+    pub nodes: Vec<ProgressNode>,
+}
+
+impl Progress {
+    /// Decodes a progress message, i.e. the node count followed by the nodes and their parents.
+    pub(crate) fn parse(message: &[u8]) -> Option<Self> {
+        let (&len, rest) = message.split_first()?;
+        let len = usize::from(len);
+        if rest.len() != len * (NODE_SIZE + 1) {
+            return None;
+        }
+        let (storage, parents) = rest.split_at(len * NODE_SIZE);
+        if parents
+            .iter()
+            .any(|&parent| usize::from(parent) >= len && parent < UNUSED_PARENT)
+        {
+            return None;
+        }
+        let nodes = storage
+            .chunks_exact(NODE_SIZE)
+            .zip(parents)
+            .map(|(node, &parent)| {
+                let count = |i: usize| u32::from_ne_bytes(node[i..i + 4].try_into().unwrap());
+                let name = &node[8..];
+                let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                ProgressNode {
+                    name: String::from_utf8_lossy(&name[..name_len]).into_owned(),
+                    completed: count(0),
+                    estimated_total: Some(count(4)).filter(|&n| n != 0 && n != u32::MAX),
+                    // Parents above the node count mark unused nodes, or the root.
+                    parent: Some(usize::from(parent)).filter(|&p| p < len),
+                }
+            })
+            .collect();
+        Some(Progress { nodes })
+    }
+
+    /// Reads the next message from `reader`.
+    ///
+    /// Returns `None` at the end of the stream, and an error of kind
+    /// [`io::ErrorKind::InvalidData`] if the message does not match the expected layout.
+    pub(crate) fn read(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut len = [0u8];
+        if reader.read(&mut len)? == 0 {
+            return Ok(None);
+        }
+        let mut message = vec![0u8; 1 + usize::from(len[0]) * (NODE_SIZE + 1)];
+        message[0] = len[0];
+        reader.read_exact(&mut message[1..])?;
+        match Progress::parse(&message) {
+            Some(progress) => Ok(Some(progress)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected layout of progress message",
+            )),
+        }
+    }
+}
+
+impl Display for Progress {
+    /// Formats the most recently started chain of nodes, e.g.
+    /// `install [1/3] > compile lib foo > LLVM Emit Object`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parent = None;
+        let mut separator = "";
+        // The depth is bounded by the node count, in case of a malformed tree.
+        for _ in 0..self.nodes.len() {
+            let Some((index, node)) = self.nodes.iter().enumerate().rev().find(|(i, node)| {
+                // Unused nodes have no name nor children.
+                node.parent == parent
+                    && (!node.name.is_empty() || self.nodes.iter().any(|n| n.parent == Some(*i)))
+            }) else {
+                break;
+            };
+            parent = Some(index);
+            if node.name.is_empty() {
+                continue;
+            }
+            write!(f, "{}{}", separator, node.name)?;
+            match node.estimated_total {
+                Some(total) => write!(f, " [{}/{}]", node.completed, total)?,
+                None if node.completed > 0 => write!(f, " [{}]", node.completed)?,
+                None => {}
+            }
+            separator = " > ";
+        }
+        Ok(())
+    }
+}
+
+/// Reads the progress messages from `reader` until the end of the stream, passing each to
+/// `callback`.
+///
+/// Unless `mode` is [`OutputMode::Quiet`], a status line prefixed with `prefix` is printed every
+/// `interval`.
+pub(crate) fn report_progress(
+    mut reader: File,
+    mode: OutputMode,
+    prefix: &str,
+    interval: Duration,
+    mut callback: Option<&mut ProgressCallback>,
+) {
+    let mut last_status = Instant::now();
+    loop {
+        let progress = match Progress::read(&mut reader) {
+            Ok(Some(progress)) => progress,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("Warning: failed to read the progress of zig: {}", e);
+                // The stream is still drained, so that zig never blocks on a full pipe.
+                let _ = io::copy(&mut reader, &mut io::sink());
+                break;
+            }
+        };
+        if let Some(callback) = &mut callback {
+            callback(&progress);
+        }
+        if mode == OutputMode::Quiet || last_status.elapsed() < interval {
+            continue;
+        }
+        let status = progress.to_string();
+        if status.is_empty() {
+            continue;
+        }
+        last_status = Instant::now();
+        eprintln!("{} progress: {}", prefix, status);
+        if mode == OutputMode::Verbose {
+            println!("cargo:warning={} progress: {}", prefix, status);
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::os::raw::c_int;
+
+    pub(super) const F_SETFD: c_int = 2;
+    pub(super) const FD_CLOEXEC: c_int = 1;
+
+    extern "C" {
+        pub(super) fn pipe(fds: *mut c_int) -> c_int;
+        pub(super) fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+    }
+}
+
+/// Creates the pipe zig reports its progress to, returning its read end and its write end.
+///
+/// Neither end is inherited by child processes, see [`inherit_progress_pipe`].
+#[cfg(unix)]
+pub(crate) fn progress_pipe() -> io::Result<(File, File)> {
+    use std::os::unix::io::{FromRawFd, RawFd};
+
+    let mut fds = [0 as RawFd; 2];
+    // SAFETY: `fds` holds the two descriptors written by `pipe`.
+    if unsafe { sys::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: both descriptors were just created and are owned by nothing else.
+    let (reader, writer) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+    for fd in fds {
+        // SAFETY: `fd` is a valid descriptor.
+        if unsafe { sys::fcntl(fd, sys::F_SETFD, sys::FD_CLOEXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok((reader, writer))
+}
+
+/// Passes the write end `writer` of the progress pipe to the child process of `cmd` only, through
+/// `ZIG_PROGRESS`.
+#[cfg(unix)]
+pub(crate) fn inherit_progress_pipe(cmd: &mut Command, writer: &File) {
+    use std::os::unix::{io::AsRawFd, process::CommandExt};
+
+    let fd = writer.as_raw_fd();
+    cmd.env("ZIG_PROGRESS", fd.to_string());
+    // SAFETY: `fcntl` is async-signal-safe, and `fd` is kept open until the child is spawned.
+    unsafe {
+        cmd.pre_exec(move || {
+            if sys::fcntl(fd, sys::F_SETFD, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Creates the pipe zig reports its progress to, which is only supported on Unix platforms.
+#[cfg(not(unix))]
+pub(crate) fn progress_pipe() -> io::Result<(File, File)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "progress reporting is only supported on Unix platforms",
+    ))
+}
+
+/// Passes the progress pipe to the child process, which is only supported on Unix platforms.
+#[cfg(not(unix))]
+pub(crate) fn inherit_progress_pipe(_cmd: &mut Command, _writer: &File) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(completed: u32, total: u32, name: &str) -> Vec<u8> {
+        let mut node = completed.to_ne_bytes().to_vec();
+        node.extend(total.to_ne_bytes());
+        node.extend(name.as_bytes());
+        node.resize(NODE_SIZE, 0);
+        node
+    }
+
+    #[test]
+    fn parse_progress_tree() {
+        let mut message = vec![4];
+        message.extend(node(1, 3, "install"));
+        message.extend(node(0, 0, "compile lib foo"));
+        message.extend(node(0, 0, "compile lib bar"));
+        message.extend(node(12, 0, "LLVM Emit Object"));
+        message.extend([255, 0, 0, 2]);
+
+        let progress = Progress::read(&mut &message[..]).unwrap().unwrap();
+        assert_eq!(progress.nodes.len(), 4);
+        assert_eq!(progress.nodes[0].estimated_total, Some(3));
+        assert_eq!(progress.nodes[0].parent, None);
+        assert_eq!(progress.nodes[3].parent, Some(2));
+        assert_eq!(
+            progress.to_string(),
+            "install [1/3] > compile lib bar > LLVM Emit Object [12]"
+        );
+
+        assert_eq!(Progress::read(&mut &[][..]).unwrap(), None);
+        assert_eq!(Progress::parse(&message[..20]), None);
+
+        // Parents are either nodes, the root or unused.
+        let last = message.len() - 1;
+        message[last] = 4;
+        assert_eq!(Progress::parse(&message), None);
+        let e = Progress::read(&mut &message[..]).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        message[last] = UNUSED_PARENT;
+        assert!(Progress::parse(&message).is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn inherit_write_end() {
+        let (mut reader, writer) = progress_pipe().unwrap();
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "printf x >&\"$ZIG_PROGRESS\""]);
+        inherit_progress_pipe(&mut cmd, &writer);
+        assert!(cmd.status().unwrap().success());
+        drop(writer);
+
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        assert_eq!(output, "x");
+    }
+}
@@ -0,0 +1,56 @@
+/// A build step declared by a zig package, as listed by `zig build -l`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BuildStep {
+    /// Name of the step.
+    pub name: String,
+    /// Description of the step.
+    pub description: String,
+    /// Whether the step is run when no step is requested.
+    pub default: bool,
+}
+
+/// Parses the output of `zig build -l`.
+pub(crate) fn parse_steps(list: &str) -> Vec<BuildStep> {
+    list.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            if name.is_empty() {
+                return None;
+            }
+            let rest = rest.trim_start();
+            let (default, description) = match rest.strip_prefix("(default)") {
+                Some(description) => (true, description.trim_start()),
+                None => (false, rest),
+            };
+            Some(BuildStep {
+                name: name.to_string(),
+                description: description.to_string(),
+                default,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_step_list() {
+        let steps = parse_steps(
+            "  install (default)            Copy build artifacts to prefix path
+  uninstall                    Remove build artifacts from prefix path
+  install-headers              Install the public headers
+  check
+",
+        );
+        let names = steps.iter().map(|s| &*s.name).collect::<Vec<_>>();
+        assert_eq!(names, ["install", "uninstall", "install-headers", "check"]);
+        assert!(steps[0].default);
+        assert_eq!(steps[0].description, "Copy build artifacts to prefix path");
+        assert!(!steps[2].default);
+        assert_eq!(steps[2].description, "Install the public headers");
+        assert_eq!(steps[3].description, "");
+    }
+}
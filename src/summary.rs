@@ -0,0 +1,310 @@
+use crate::json::Value;
+use std::time::Duration;
+
+/// Amount of steps reported in the build summary, passed to `zig build --summary`.
+#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum SummaryMode {
+    /// Report all steps.
+    #[default]
+    All,
+    /// Report the steps which were not cached.
+    New,
+    /// Report the steps which failed.
+    Failures,
+    /// Do not report a summary.
+    None,
+}
+
+impl SummaryMode {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SummaryMode::All => "all",
+            SummaryMode::New => "new",
+            SummaryMode::Failures => "failures",
+            SummaryMode::None => "none",
+        }
+    }
+}
+
+/// Outcome of a build step.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum StepStatus {
+    /// The step was up to date.
+    Cached,
+    /// The step was run successfully.
+    Success,
+    /// The step, or one of its dependencies, failed.
+    Failed,
+    /// The step was skipped.
+    Skipped,
+}
+
+impl StepStatus {
+    fn name(self) -> &'static str {
+        match self {
+            StepStatus::Cached => "cached",
+            StepStatus::Success => "success",
+            StepStatus::Failed => "failed",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// A step of the build summary, with the steps it depends on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepSummary {
+    /// Name of the step, e.g. `zig build-lib foo Debug native`.
+    pub name: String,
+    /// Outcome of the step.
+    pub status: StepStatus,
+    /// Time spent running the step.
+    pub duration: Option<Duration>,
+    /// Peak resident set size of the step, in bytes.
+    pub max_rss: Option<u64>,
+    /// Steps the step depends on.
+    pub dependencies: Vec<StepSummary>,
+}
+
+/// Summary of a `zig build` run, as reported with `--summary`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildSummary {
+    /// Number of steps which succeeded.
+    pub succeeded: usize,
+    /// Total number of steps.
+    pub total: usize,
+    /// Requested steps, with their dependencies.
+    pub steps: Vec<StepSummary>,
+}
+
+impl BuildSummary {
+    /// Parses the summary from the standard error of `zig build`.
+    ///
+    /// Returns `None` if the output contains no summary.
+    pub(crate) fn parse(output: &str) -> Option<Self> {
+        let mut lines = output
+            .lines()
+            .skip_while(|line| !line.starts_with("Build Summary:"));
+        let header = lines.next()?.trim_start_matches("Build Summary:").trim();
+        let (succeeded, total) = header
+            .split_whitespace()
+            .next()
+            .and_then(|counts| counts.split_once('/'))
+            .and_then(|(s, t)| Some((s.parse().ok()?, t.parse().ok()?)))
+            .unwrap_or_default();
+
+        let mut summary = BuildSummary {
+            succeeded,
+            total,
+            steps: vec![],
+        };
+        for line in lines {
+            let name_start = line
+                .find(|c: char| !matches!(c, ' ' | '|' | '+' | '-' | '│' | '├' | '└' | '─'))
+                .unwrap_or(line.len());
+            let Some(step) = parse_step(&line[name_start..]) else {
+                break;
+            };
+            // Each level of the tree is indented by three columns.
+            let depth = line[..name_start].chars().count() / 3;
+            let mut siblings = &mut summary.steps;
+            for _ in 0..depth {
+                if siblings.is_empty() {
+                    break;
+                }
+                siblings = &mut siblings.last_mut().unwrap().dependencies;
+            }
+            siblings.push(step);
+        }
+        Some(summary)
+    }
+
+    /// Iterates over all steps of the summary, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &StepSummary> {
+        let mut stack = self.steps.iter().rev().collect::<Vec<_>>();
+        std::iter::from_fn(move || {
+            let step = stack.pop()?;
+            stack.extend(step.dependencies.iter().rev());
+            Some(step)
+        })
+    }
+
+    /// Serializes the summary to JSON.
+    pub fn to_json(&self) -> String {
+        fn step_json(step: &StepSummary) -> Value {
+            let optional = |value: Option<f64>| value.map_or(Value::Null, Value::Number);
+            Value::Object(vec![
+                ("name".to_string(), Value::String(step.name.clone())),
+                (
+                    "status".to_string(),
+                    Value::String(step.status.name().to_string()),
+                ),
+                (
+                    "duration_ms".to_string(),
+                    optional(step.duration.map(|d| d.as_secs_f64() * 1000.0)),
+                ),
+                (
+                    "max_rss".to_string(),
+                    optional(step.max_rss.map(|rss| rss as f64)),
+                ),
+                (
+                    "dependencies".to_string(),
+                    Value::Array(step.dependencies.iter().map(step_json).collect()),
+                ),
+            ])
+        }
+
+        Value::Object(vec![
+            (
+                "succeeded".to_string(),
+                Value::Number(self.succeeded as f64),
+            ),
+            ("total".to_string(), Value::Number(self.total as f64)),
+            (
+                "steps".to_string(),
+                Value::Array(self.steps.iter().map(step_json).collect()),
+            ),
+        ])
+        .to_string()
+    }
+}
+
+/// Parses a line of the summary tree, without its indentation.
+fn parse_step(line: &str) -> Option<StepSummary> {
+    let mut words = line.split_whitespace().collect::<Vec<_>>();
+    if words.last() == Some(&"(reused)") {
+        words.pop();
+    }
+
+    let mut max_rss = None;
+    let mut duration = None;
+    while let Some(word) = words.last() {
+        if let Some(rss) = word.strip_prefix("MaxRSS:") {
+            max_rss = parse_size(rss);
+        } else if let Some(d) = parse_duration(word) {
+            duration = Some(d);
+        } else {
+            break;
+        }
+        words.pop();
+    }
+
+    let counted = words.len() >= 3 && words[words.len() - 2].parse::<u64>().is_ok();
+    let mut status = match *words.last()? {
+        _ if counted => None,
+        "cached" => Some(StepStatus::Cached),
+        "success" => Some(StepStatus::Success),
+        "skipped" => Some(StepStatus::Skipped),
+        "failure" => Some(StepStatus::Failed),
+        _ => None,
+    };
+    if status.is_some() {
+        words.pop();
+        if status == Some(StepStatus::Failed) && words.last() == Some(&"transitive") {
+            words.pop();
+        }
+    } else {
+        // Counts of errors or test results, e.g. `2 errors` or `3 passed 1 failed`.
+        while words.len() >= 3 && words[words.len() - 2].parse::<u64>().is_ok() {
+            let count = words.pop()?;
+            words.pop();
+            status = match (count, status) {
+                (_, Some(StepStatus::Failed)) => Some(StepStatus::Failed),
+                ("passed" | "skipped", _) => Some(StepStatus::Success),
+                _ => Some(StepStatus::Failed),
+            };
+        }
+    }
+    if words.is_empty() {
+        return None;
+    }
+    Some(StepSummary {
+        name: words.join(" "),
+        status: status?,
+        duration,
+        max_rss,
+        dependencies: vec![],
+    })
+}
+
+/// Parses a duration as printed by zig, e.g. `12ms`.
+fn parse_duration(word: &str) -> Option<Duration> {
+    let split = word.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let value = word[..split].parse::<f64>().ok()?;
+    let nanos = match &word[split..] {
+        "ns" => 1.0,
+        "us" | "µs" => 1e3,
+        "ms" => 1e6,
+        "s" => 1e9,
+        "m" | "min" => 60e9,
+        "h" => 3600e9,
+        _ => return None,
+    };
+    Some(Duration::from_nanos((value * nanos) as u64))
+}
+
+/// Parses a size as printed by zig, e.g. `26M` or `1.5MiB`.
+fn parse_size(size: &str) -> Option<u64> {
+    let split = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let value = size[..split].parse::<f64>().ok()?;
+    let unit = match size[split..].chars().next() {
+        None | Some('B') => 1u64,
+        Some('K' | 'k') => 1 << 10,
+        Some('M') => 1 << 20,
+        Some('G') => 1 << 30,
+        Some('T') => 1 << 40,
+        _ => return None,
+    };
+    Some((value * unit as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_summary_tree() {
+        let output = "\
+info: some output
+Build Summary: 4/5 steps succeeded; 1 failed
+install transitive failure
+└─ install zig_package transitive failure
+   ├─ zig build-lib zig_package Debug native 2 errors
+   └─ zig build-lib helper Debug native cached 9ms MaxRSS:26M
+test success
++- run test 3 passed 1 skipped 120ms MaxRSS:2M
+error: the following build command failed with exit code 1:
+";
+        let summary = BuildSummary::parse(output).unwrap();
+        assert_eq!((summary.succeeded, summary.total), (4, 5));
+        assert_eq!(summary.steps.len(), 2);
+
+        let install = &summary.steps[0];
+        assert_eq!(install.name, "install");
+        assert_eq!(install.status, StepStatus::Failed);
+        let lib = &install.dependencies[0];
+        assert_eq!(lib.name, "install zig_package");
+        assert_eq!(lib.dependencies[0].status, StepStatus::Failed);
+        let helper = &lib.dependencies[1];
+        assert_eq!(helper.name, "zig build-lib helper Debug native");
+        assert_eq!(helper.status, StepStatus::Cached);
+        assert_eq!(helper.duration, Some(Duration::from_millis(9)));
+        assert_eq!(helper.max_rss, Some(26 << 20));
+
+        let run = &summary.steps[1].dependencies[0];
+        assert_eq!(run.name, "run test");
+        assert_eq!(run.status, StepStatus::Success);
+        assert_eq!(summary.iter().count(), 6);
+
+        let json = Value::parse(&summary.to_json()).unwrap();
+        assert_eq!(json.get("total"), Some(&Value::Number(5.0)));
+        let Some(Value::Array(steps)) = json.get("steps") else {
+            panic!("expected an array of steps");
+        };
+        assert_eq!(
+            steps[1].get("status").and_then(Value::as_str),
+            Some("success")
+        );
+    }
+}
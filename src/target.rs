@@ -0,0 +1,655 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// A Zig target, composed of the CPU architecture, operating system and ABI.
+///
+/// The target is formatted with the syntax expected by `-Dtarget`, e.g. `x86_64-linux-gnu.2.17`
+/// or `aarch64-macos.11.0...14.0`.
+///
+/// # Examples
+///
+/// ```
+/// use zigcli::{Target, Version};
+///
+/// let mut target: Target = "x86_64-linux-gnu".parse().unwrap();
+/// target.glibc_version = Some(Version::new(2, 17));
+/// assert_eq!(target.to_string(), "x86_64-linux-gnu.2.17");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Target {
+    /// CPU architecture, e.g. `x86_64`.
+    pub arch: String,
+    /// Operating system, e.g. `linux`.
+    pub os: String,
+    /// Range of supported operating system versions.
+    pub os_version_range: Option<OsVersionRange>,
+    /// ABI, e.g. `gnu`.
+    pub abi: Option<String>,
+    /// Version of glibc to link against, only used for `gnu` ABIs.
+    pub glibc_version: Option<Version>,
+}
+
+/// Range of operating system versions, formatted as `min...max`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct OsVersionRange {
+    /// Minimum supported version.
+    pub min: OsVersion,
+    /// Maximum supported version.
+    pub max: Option<OsVersion>,
+}
+
+/// Version of an operating system.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum OsVersion {
+    /// Semantic version, used by most operating systems.
+    Semantic(Version),
+    /// Named version, used by Windows, e.g. `win10`.
+    Named(String),
+}
+
+/// Semantic version of the form `major.minor[.patch]`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct Version {
+    /// Major version.
+    pub major: u32,
+    /// Minor version.
+    pub minor: u32,
+    /// Patch version, if specified.
+    pub patch: Option<u32>,
+}
+
+/// Error returned when parsing a [`Target`] or [`Version`] fails.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseTargetError {
+    message: String,
+}
+
+impl Target {
+    /// Creates a target without version requirements.
+    pub fn new(arch: impl Into<String>, os: impl Into<String>, abi: Option<&str>) -> Self {
+        Self {
+            arch: arch.into(),
+            os: os.into(),
+            os_version_range: None,
+            abi: abi.map(str::to_string),
+            glibc_version: None,
+        }
+    }
+
+    /// Translates the Rust target triple `triple` to a Zig target.
+    ///
+    /// Triples unknown to this crate are translated by mapping their components individually.
+    pub fn from_rust_triple(triple: &str) -> Option<Self> {
+        let zig_target = match translate_target_triple(triple) {
+            Some(zig_target) => zig_target.to_string(),
+            None => guess_target_triple(triple)?,
+        };
+        zig_target.parse().ok()
+    }
+}
+
+impl FromStr for Target {
+    type Err = ParseTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "native" {
+            return Ok(Target::new("native", "native", None));
+        }
+
+        let mut parts = s.split('-');
+        let arch = parts.next().unwrap_or_default();
+        let (os, os_version_range) = match parts.next() {
+            Some(os) => match os.split_once('.') {
+                Some((os, range)) => (os, Some(range.parse()?)),
+                None => (os, None),
+            },
+            None => return Err(ParseTargetError::new(format!("missing os in target `{s}`"))),
+        };
+        let (abi, glibc_version) = match parts.next() {
+            Some(abi) => match abi.split_once('.') {
+                Some((abi, version)) => (Some(abi), Some(version.parse()?)),
+                None => (Some(abi), None),
+            },
+            None => (None, None),
+        };
+        if parts.next().is_some() || arch.is_empty() || os.is_empty() || abi == Some("") {
+            return Err(ParseTargetError::new(format!("invalid target `{s}`")));
+        }
+
+        Ok(Target {
+            arch: arch.to_string(),
+            os: os.to_string(),
+            os_version_range,
+            abi: abi.map(str::to_string),
+            glibc_version,
+        })
+    }
+}
+
+impl TryFrom<&str> for Target {
+    type Error = ParseTargetError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for Target {
+    type Error = ParseTargetError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.arch == "native" && self.os == "native" && self.abi.is_none() {
+            return write!(f, "native");
+        }
+
+        write!(f, "{}-{}", self.arch, self.os)?;
+        if let Some(range) = &self.os_version_range {
+            write!(f, ".{range}")?;
+        }
+        if let Some(abi) = &self.abi {
+            write!(f, "-{abi}")?;
+            if let Some(glibc_version) = &self.glibc_version {
+                write!(f, ".{glibc_version}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OsVersionRange {
+    type Err = ParseTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = match s.split_once("...") {
+            Some((min, max)) => (min.parse()?, Some(max.parse()?)),
+            None => (s.parse()?, None),
+        };
+        Ok(OsVersionRange { min, max })
+    }
+}
+
+impl fmt::Display for OsVersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.min)?;
+        if let Some(max) = &self.max {
+            write!(f, "...{max}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Version> for OsVersion {
+    fn from(value: Version) -> Self {
+        OsVersion::Semantic(value)
+    }
+}
+
+impl FromStr for OsVersion {
+    type Err = ParseTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with(|c: char| c.is_ascii_digit()) {
+            s.parse().map(OsVersion::Semantic)
+        } else if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            Ok(OsVersion::Named(s.to_string()))
+        } else {
+            Err(ParseTargetError::new(format!("invalid os version `{s}`")))
+        }
+    }
+}
+
+impl fmt::Display for OsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OsVersion::Semantic(version) => write!(f, "{version}"),
+            OsVersion::Named(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl Version {
+    /// Creates a version of the form `major.minor`.
+    pub fn new(major: u32, minor: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch: None,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = ParseTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseTargetError::new(format!("invalid version `{s}`"));
+        let mut parts = s
+            .split('.')
+            .map(|part| part.parse::<u32>().map_err(|_| invalid()));
+        let major = parts.next().ok_or_else(invalid)??;
+        let minor = parts.next().transpose()?.unwrap_or(0);
+        let patch = parts.next().transpose()?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Version {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if let Some(patch) = self.patch {
+            write!(f, ".{patch}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ParseTargetError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Display for ParseTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ParseTargetError {}
+
+pub(crate) fn translate_target_triple(target: &str) -> Option<&'static str> {
+    let translated = match target {
+        "aarch64-apple-darwin" => "aarch64-macos",
+        "aarch64-apple-ios" => "aarch64-ios",
+        "aarch64-apple-ios-macabi" => "aarch64-ios-macabi",
+        "aarch64-apple-ios-sim" => "aarch64-ios-simulator",
+        "aarch64-apple-tvos" => "aarch64-tvos",
+        "aarch64-apple-tvos-sim" => "aarch64-tvos-simulator",
+        "aarch64-apple-visionos" => "aarch64-visionos",
+        "aarch64-apple-visionos-sim" => "aarch64-visionos-simulator",
+        "aarch64-apple-watchos" => "aarch64-watchos",
+        "aarch64-apple-watchos-sim" => "aarch64-watchos-simulator",
+        "aarch64-linux-android" => "aarch64-linux-android",
+        "aarch64-pc-windows-gnullvm" => "aarch64-windows-gnu",
+        "aarch64-pc-windows-msvc" => "aarch64-windows-msvc",
+        "aarch64-unknown-freebsd" => "aarch64-freebsd",
+        "aarch64-unknown-fuchsia" => "aarch64-fuchsia",
+        "aarch64-unknown-hermit" => "aarch64-hermit",
+        "aarch64-unknown-illumos" => "aarch64-illumos",
+        "aarch64-unknown-linux-gnu" => "aarch64-linux-gnu",
+        "aarch64-unknown-linux-gnu_ilp32" => "aarch64-linux-gnuilp32",
+        "aarch64-unknown-linux-musl" => "aarch64-linux-musl",
+        "aarch64-unknown-linux-ohos" => "aarch64-linux-ohos",
+        "aarch64-unknown-netbsd" => "aarch64-netbsd",
+        "aarch64-unknown-none" => "aarch64-freestanding",
+        "aarch64-unknown-openbsd" => "aarch64-openbsd",
+        "aarch64-unknown-uefi" => "aarch64-uefi",
+        "aarch64_be-unknown-linux-gnu" => "aarch64_be-linux-gnu",
+        "aarch64_be-unknown-linux-gnu_ilp32" => "aarch64_be-linux-gnuilp32",
+        "aarch64_be-unknown-netbsd" => "aarch64_be-netbsd",
+        "arm-linux-androideabi" => "arm-linux-androideabi",
+        "arm-unknown-linux-gnueabi" => "arm-linux-gnueabi",
+        "arm-unknown-linux-gnueabihf" => "arm-linux-gnueabihf",
+        "arm-unknown-linux-musleabi" => "arm-linux-musleabi",
+        "arm-unknown-linux-musleabihf" => "arm-linux-musleabihf",
+        "armebv7r-none-eabi" => "armeb-freestanding-eabi",
+        "armebv7r-none-eabihf" => "armeb-freestanding-eabihf",
+        "armv4t-unknown-linux-gnueabi" => "arm-linux-gnueabi",
+        "armv5te-unknown-linux-gnueabi" => "arm-linux-gnueabi",
+        "armv5te-unknown-linux-musleabi" => "arm-linux-musleabi",
+        "armv6-unknown-freebsd" => "arm-freebsd-eabihf",
+        "armv6-unknown-netbsd-eabihf" => "arm-netbsd-eabihf",
+        "armv7-linux-androideabi" => "arm-linux-androideabi",
+        "armv7-unknown-freebsd" => "arm-freebsd-eabihf",
+        "armv7-unknown-linux-gnueabi" => "arm-linux-gnueabi",
+        "armv7-unknown-linux-gnueabihf" => "arm-linux-gnueabihf",
+        "armv7-unknown-linux-musleabi" => "arm-linux-musleabi",
+        "armv7-unknown-linux-musleabihf" => "arm-linux-musleabihf",
+        "armv7-unknown-netbsd-eabihf" => "arm-netbsd-eabihf",
+        "armv7a-none-eabi" => "arm-freestanding-eabi",
+        "armv7a-none-eabihf" => "arm-freestanding-eabihf",
+        "armv7r-none-eabi" => "arm-freestanding-eabi",
+        "armv7r-none-eabihf" => "arm-freestanding-eabihf",
+        "armv8r-none-eabihf" => "arm-freestanding-eabihf",
+        "bpfeb-unknown-none" => "bpfeb-freestanding",
+        "bpfel-unknown-none" => "bpfel-freestanding",
+        "i386-apple-ios" => "x86-ios",
+        "i586-unknown-linux-gnu" => "x86-linux-gnu",
+        "i586-unknown-linux-musl" => "x86-linux-musl",
+        "i686-apple-darwin" => "x86-macos",
+        "i686-linux-android" => "x86-linux-android",
+        "i686-pc-windows-gnu" => "x86-windows-gnu",
+        "i686-pc-windows-gnullvm" => "x86-windows-gnu",
+        "i686-pc-windows-msvc" => "x86-windows-msvc",
+        "i686-unknown-freebsd" => "x86-freebsd",
+        "i686-unknown-haiku" => "x86-haiku",
+        "i686-unknown-hurd-gnu" => "x86-hurd-gnu",
+        "i686-unknown-linux-gnu" => "x86-linux-gnu",
+        "i686-unknown-linux-musl" => "x86-linux-musl",
+        "i686-unknown-netbsd" => "x86-netbsd",
+        "i686-unknown-openbsd" => "x86-openbsd",
+        "i686-unknown-uefi" => "x86-uefi",
+        "loongarch64-unknown-linux-gnu" => "loongarch64-linux-gnu",
+        "loongarch64-unknown-linux-musl" => "loongarch64-linux-musl",
+        "loongarch64-unknown-linux-ohos" => "loongarch64-linux-ohos",
+        "loongarch64-unknown-none" => "loongarch64-freestanding",
+        "mips-unknown-linux-gnu" => "mips-linux-gnueabihf",
+        "mips-unknown-linux-musl" => "mips-linux-musleabihf",
+        "mips64-unknown-linux-gnuabi64" => "mips64-linux-gnuabi64",
+        "mips64-unknown-linux-muslabi64" => "mips64-linux-muslabi64",
+        "mips64el-unknown-linux-gnuabi64" => "mips64el-linux-gnuabi64",
+        "mips64el-unknown-linux-muslabi64" => "mips64el-linux-muslabi64",
+        "mipsel-unknown-linux-gnu" => "mipsel-linux-gnueabihf",
+        "mipsel-unknown-linux-musl" => "mipsel-linux-musleabihf",
+        "mipsel-unknown-netbsd" => "mipsel-netbsd-eabihf",
+        "mipsel-unknown-none" => "mipsel-freestanding",
+        "mipsisa32r6-unknown-linux-gnu" => "mips-linux-gnueabihf",
+        "mipsisa32r6el-unknown-linux-gnu" => "mipsel-linux-gnueabihf",
+        "mipsisa64r6-unknown-linux-gnuabi64" => "mips64-linux-gnuabi64",
+        "mipsisa64r6el-unknown-linux-gnuabi64" => "mips64el-linux-gnuabi64",
+        "powerpc-unknown-freebsd" => "powerpc-freebsd-eabihf",
+        "powerpc-unknown-linux-gnu" => "powerpc-linux-gnueabihf",
+        "powerpc-unknown-linux-gnuspe" => "powerpc-linux-gnueabi",
+        "powerpc-unknown-linux-musl" => "powerpc-linux-musleabihf",
+        "powerpc-unknown-netbsd" => "powerpc-netbsd-eabihf",
+        "powerpc-unknown-openbsd" => "powerpc-openbsd-eabihf",
+        "powerpc64-ibm-aix" => "powerpc64-aix",
+        "powerpc64-unknown-freebsd" => "powerpc64-freebsd",
+        "powerpc64-unknown-linux-gnu" => "powerpc64-linux-gnu",
+        "powerpc64-unknown-linux-musl" => "powerpc64-linux-musl",
+        "powerpc64-unknown-openbsd" => "powerpc64-openbsd",
+        "powerpc64le-unknown-freebsd" => "powerpc64le-freebsd",
+        "powerpc64le-unknown-linux-gnu" => "powerpc64le-linux-gnu",
+        "powerpc64le-unknown-linux-musl" => "powerpc64le-linux-musl",
+        "riscv32gc-unknown-linux-gnu" => "riscv32-linux-gnu",
+        "riscv32gc-unknown-linux-musl" => "riscv32-linux-musl",
+        "riscv32i-unknown-none-elf" => "riscv32-freestanding",
+        "riscv32im-unknown-none-elf" => "riscv32-freestanding",
+        "riscv32ima-unknown-none-elf" => "riscv32-freestanding",
+        "riscv32imac-unknown-none-elf" => "riscv32-freestanding",
+        "riscv32imafc-unknown-none-elf" => "riscv32-freestanding",
+        "riscv32imc-unknown-none-elf" => "riscv32-freestanding",
+        "riscv64-linux-android" => "riscv64-linux-android",
+        "riscv64gc-unknown-freebsd" => "riscv64-freebsd",
+        "riscv64gc-unknown-fuchsia" => "riscv64-fuchsia",
+        "riscv64gc-unknown-hermit" => "riscv64-hermit",
+        "riscv64gc-unknown-linux-gnu" => "riscv64-linux-gnu",
+        "riscv64gc-unknown-linux-musl" => "riscv64-linux-musl",
+        "riscv64gc-unknown-netbsd" => "riscv64-netbsd",
+        "riscv64gc-unknown-none-elf" => "riscv64-freestanding",
+        "riscv64gc-unknown-openbsd" => "riscv64-openbsd",
+        "riscv64imac-unknown-none-elf" => "riscv64-freestanding",
+        "s390x-unknown-linux-gnu" => "s390x-linux-gnu",
+        "s390x-unknown-linux-musl" => "s390x-linux-musl",
+        "sparc-unknown-linux-gnu" => "sparc-linux-gnu",
+        "sparc-unknown-none-elf" => "sparc-freestanding",
+        "sparc64-unknown-linux-gnu" => "sparc64-linux-gnu",
+        "sparc64-unknown-netbsd" => "sparc64-netbsd",
+        "sparc64-unknown-openbsd" => "sparc64-openbsd",
+        "sparcv9-sun-solaris" => "sparc64-solaris",
+        "thumbv4t-none-eabi" => "thumb-freestanding-eabi",
+        "thumbv5te-none-eabi" => "thumb-freestanding-eabi",
+        "thumbv6m-none-eabi" => "thumb-freestanding-eabi",
+        "thumbv7a-pc-windows-msvc" => "thumb-windows-msvc",
+        "thumbv7em-none-eabi" => "thumb-freestanding-eabi",
+        "thumbv7em-none-eabihf" => "thumb-freestanding-eabihf",
+        "thumbv7m-none-eabi" => "thumb-freestanding-eabi",
+        "thumbv7neon-linux-androideabi" => "thumb-linux-androideabi",
+        "thumbv7neon-unknown-linux-gnueabihf" => "thumb-linux-gnueabihf",
+        "thumbv8m.base-none-eabi" => "thumb-freestanding-eabi",
+        "thumbv8m.main-none-eabi" => "thumb-freestanding-eabi",
+        "thumbv8m.main-none-eabihf" => "thumb-freestanding-eabihf",
+        "wasm32-unknown-emscripten" => "wasm32-emscripten",
+        "wasm32-unknown-unknown" => "wasm32-freestanding",
+        "wasm32-wasi" => "wasm32-wasi",
+        "wasm32-wasip1" => "wasm32-wasi",
+        "wasm32-wasip1-threads" => "wasm32-wasi",
+        "wasm32-wasip2" => "wasm32-wasi",
+        "wasm32v1-none" => "wasm32-freestanding",
+        "wasm64-unknown-unknown" => "wasm64-freestanding",
+        "x86_64-apple-darwin" => "x86_64-macos",
+        "x86_64-apple-ios" => "x86_64-ios",
+        "x86_64-apple-ios-macabi" => "x86_64-ios-macabi",
+        "x86_64-apple-tvos" => "x86_64-tvos",
+        "x86_64-apple-watchos-sim" => "x86_64-watchos-simulator",
+        "x86_64-linux-android" => "x86_64-linux-android",
+        "x86_64-pc-solaris" => "x86_64-solaris",
+        "x86_64-pc-windows-gnu" => "x86_64-windows-gnu",
+        "x86_64-pc-windows-gnullvm" => "x86_64-windows-gnu",
+        "x86_64-pc-windows-msvc" => "x86_64-windows-msvc",
+        "x86_64-unknown-dragonfly" => "x86_64-dragonfly",
+        "x86_64-unknown-freebsd" => "x86_64-freebsd",
+        "x86_64-unknown-fuchsia" => "x86_64-fuchsia",
+        "x86_64-unknown-haiku" => "x86_64-haiku",
+        "x86_64-unknown-hermit" => "x86_64-hermit",
+        "x86_64-unknown-hurd-gnu" => "x86_64-hurd-gnu",
+        "x86_64-unknown-illumos" => "x86_64-illumos",
+        "x86_64-unknown-linux-gnu" => "x86_64-linux-gnu",
+        "x86_64-unknown-linux-gnux32" => "x86_64-linux-gnux32",
+        "x86_64-unknown-linux-musl" => "x86_64-linux-musl",
+        "x86_64-unknown-linux-none" => "x86_64-linux-none",
+        "x86_64-unknown-linux-ohos" => "x86_64-linux-ohos",
+        "x86_64-unknown-netbsd" => "x86_64-netbsd",
+        "x86_64-unknown-none" => "x86_64-freestanding",
+        "x86_64-unknown-openbsd" => "x86_64-openbsd",
+        "x86_64-unknown-uefi" => "x86_64-uefi",
+        _ => return None,
+    };
+    Some(translated)
+}
+
+/// Guesses the zig target of an unknown Rust target triple, by translating its architecture,
+/// operating system and environment independently.
+pub(crate) fn guess_target_triple(target: &str) -> Option<String> {
+    let mut parts = target.split('-').collect::<Vec<_>>();
+    if parts.len() < 2 {
+        return None;
+    }
+
+    // Rust triples take the form `arch-vendor-os-env`, where the vendor and environment are
+    // optional, e.g. `aarch64-linux-android` or `thumbv7em-none-eabihf`.
+    const VENDORS: &[&str] = &[
+        "apple", "esp", "fortanix", "ibm", "kmc", "nintendo", "nvidia", "openwrt", "pc", "sony",
+        "sun", "unknown", "uwp", "win7", "wrs",
+    ];
+    if parts.len() > 2 && VENDORS.contains(&parts[1]) {
+        parts.remove(1);
+    }
+
+    let arch = match parts[0] {
+        "i386" | "i486" | "i586" | "i686" => "x86",
+        "x86_64h" => "x86_64",
+        "arm64e" => "aarch64",
+        "sparcv9" => "sparc64",
+        "wasm32v1" => "wasm32",
+        "mipsisa32r6" => "mips",
+        "mipsisa32r6el" => "mipsel",
+        "mipsisa64r6" => "mips64",
+        "mipsisa64r6el" => "mips64el",
+        arch if arch.starts_with("armeb") => "armeb",
+        arch if arch.starts_with("arm") => "arm",
+        arch if arch.starts_with("thumbeb") => "thumbeb",
+        arch if arch.starts_with("thumb") => "thumb",
+        arch if arch.starts_with("riscv32") => "riscv32",
+        arch if arch.starts_with("riscv64") => "riscv64",
+        arch => arch,
+    };
+
+    let os = match parts[1] {
+        "darwin" => "macos",
+        "none" | "unknown" => "freestanding",
+        "wasi" | "wasip1" | "wasip2" => "wasi",
+        os => os,
+    };
+
+    let abi = match parts.get(2).copied().unwrap_or("") {
+        "" | "elf" | "softfloat" | "threads" => None,
+        "gnullvm" => Some("gnu"),
+        "gnuspe" => Some("gnueabi"),
+        "gnu_ilp32" => Some("gnuilp32"),
+        "sim" => Some("simulator"),
+        // Zig distinguishes the float ABI of 32-bit MIPS and PowerPC Linux targets.
+        abi @ ("gnu" | "musl") if matches!(arch, "mips" | "mipsel" | "powerpc") => {
+            return Some(format!("{arch}-{os}-{abi}eabihf"));
+        }
+        abi => Some(abi),
+    };
+
+    match abi {
+        Some(abi) => Some(format!("{arch}-{os}-{abi}")),
+        None => Some(format!("{arch}-{os}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        for target in [
+            "native",
+            "x86_64-linux-gnu",
+            "x86_64-linux-gnu.2.17",
+            "aarch64-macos.11.0...14.0",
+            "x86_64-linux.4.19...6.1-musl",
+            "x86_64-windows.win10...win11_dt-msvc",
+            "thumb-freestanding-eabihf",
+        ] {
+            assert_eq!(target.parse::<Target>().unwrap().to_string(), target);
+        }
+        assert_eq!(
+            "aarch64-ios.16".parse::<Target>().unwrap().to_string(),
+            "aarch64-ios.16.0"
+        );
+        assert!("x86_64".parse::<Target>().is_err());
+        assert!("x86_64-linux-gnu-extra".parse::<Target>().is_err());
+        assert!("x86_64-linux-gnu.two".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn translate_targets() {
+        for (rust_target, zig_target, cpu) in [
+            (
+                "armv7-unknown-linux-gnueabihf",
+                "arm-linux-gnueabihf",
+                "generic+v7a+vfp3d16",
+            ),
+            (
+                "armebv7r-none-eabihf",
+                "armeb-freestanding-eabihf",
+                "cortex_r4f",
+            ),
+            (
+                "thumbv6m-none-eabi",
+                "thumb-freestanding-eabi",
+                "cortex_m0plus",
+            ),
+            (
+                "thumbv7em-none-eabi",
+                "thumb-freestanding-eabi",
+                "cortex_m4+soft_float",
+            ),
+            (
+                "thumbv8m.main-none-eabihf",
+                "thumb-freestanding-eabihf",
+                "cortex_m33",
+            ),
+            (
+                "riscv32imac-unknown-none-elf",
+                "riscv32-freestanding",
+                "generic_rv32+m+a+c",
+            ),
+            (
+                "riscv64gc-unknown-linux-gnu",
+                "riscv64-linux-gnu",
+                "baseline_rv64",
+            ),
+            ("wasm32-unknown-unknown", "wasm32-freestanding", "generic"),
+            ("wasm32-wasip1-threads", "wasm32-wasi", "generic"),
+            ("wasm32v1-none", "wasm32-freestanding", "mvp"),
+            ("mips-unknown-linux-gnu", "mips-linux-gnueabihf", "mips32r2"),
+            (
+                "mips64el-unknown-linux-muslabi64",
+                "mips64el-linux-muslabi64",
+                "mips64r2",
+            ),
+            (
+                "mipsisa32r6el-unknown-linux-gnu",
+                "mipsel-linux-gnueabihf",
+                "mips32r6",
+            ),
+            (
+                "powerpc-unknown-linux-gnuspe",
+                "powerpc-linux-gnueabi",
+                "e500",
+            ),
+            (
+                "powerpc64le-unknown-linux-gnu",
+                "powerpc64le-linux-gnu",
+                "ppc64le",
+            ),
+            ("powerpc64-ibm-aix", "powerpc64-aix", "pwr7"),
+            ("s390x-unknown-linux-gnu", "s390x-linux-gnu", "z10"),
+            ("sparc-unknown-none-elf", "sparc-freestanding", "leon3"),
+            ("sparcv9-sun-solaris", "sparc64-solaris", "v9"),
+        ] {
+            assert_eq!(
+                translate_target_triple(rust_target),
+                Some(zig_target),
+                "{rust_target}"
+            );
+            assert!(zig_target.parse::<Target>().is_ok(), "{zig_target}");
+            let model = crate::cpu::translate_target_cpu_model(rust_target);
+            assert_eq!(model, cpu, "{rust_target}");
+            assert!(model.parse::<crate::Cpu>().is_ok(), "{model}");
+        }
+        assert_eq!(translate_target_triple("x86_64-unknown-redox"), None);
+    }
+
+    #[test]
+    fn guess_targets() {
+        let guess = guess_target_triple;
+        assert_eq!(
+            guess("armv7-unknown-linux-gnueabihf").as_deref(),
+            Some("arm-linux-gnueabihf")
+        );
+        assert_eq!(
+            guess("thumbv7em-none-eabihf").as_deref(),
+            Some("thumb-freestanding-eabihf")
+        );
+        assert_eq!(
+            guess("riscv32imc-unknown-none-elf").as_deref(),
+            Some("riscv32-freestanding")
+        );
+        assert_eq!(
+            guess("x86_64-unknown-redox").as_deref(),
+            Some("x86_64-redox")
+        );
+        assert_eq!(
+            guess("aarch64-linux-android").as_deref(),
+            Some("aarch64-linux-android")
+        );
+        assert_eq!(
+            guess("mipsel-unknown-linux-musl").as_deref(),
+            Some("mipsel-linux-musleabihf")
+        );
+        assert_eq!(
+            guess("i686-pc-windows-gnullvm").as_deref(),
+            Some("x86-windows-gnu")
+        );
+        assert_eq!(guess("custom"), None);
+    }
+}
@@ -0,0 +1,241 @@
+//! Support for custom Rust target specifications.
+
+use crate::{
+    cpu::{translate_arch_cpu, translate_arch_feature},
+    json::Value,
+    target::guess_target_triple,
+    Cpu,
+};
+use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Subset of a custom Rust target specification, as read from its JSON file.
+#[derive(Debug, Clone)]
+pub(crate) struct TargetSpec {
+    llvm_target: String,
+    arch: String,
+    os: Option<String>,
+    env: Option<String>,
+    cpu: Option<String>,
+    features: Option<String>,
+    data_layout: Option<String>,
+}
+
+impl TargetSpec {
+    /// Locates the specification of the custom target `target`.
+    ///
+    /// The target may either be a path to the JSON file, or its name, in which case the file is
+    /// searched in the working directory and then in the directories listed in `search_path`, as
+    /// `rustc` does with `RUST_TARGET_PATH`.
+    pub(crate) fn find(target: &str, search_path: Option<&OsStr>) -> Option<PathBuf> {
+        let path = Path::new(target);
+        if path.extension() == Some(OsStr::new("json")) && path.is_file() {
+            return Some(path.to_path_buf());
+        }
+
+        let search_path = search_path.map(env::split_paths).into_iter().flatten();
+        env::current_dir()
+            .into_iter()
+            .chain(search_path)
+            .map(|dir| dir.join(format!("{target}.json")))
+            .find(|path| path.is_file())
+    }
+
+    /// Reads the specification from the JSON file at `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let spec = Value::parse(contents)?;
+
+        let string = |key: &str| spec.get(key).and_then(Value::as_str).map(str::to_string);
+        Ok(Self {
+            llvm_target: string("llvm-target").ok_or("missing `llvm-target` key")?,
+            arch: string("arch").ok_or("missing `arch` key")?,
+            os: string("os"),
+            env: string("env"),
+            cpu: string("cpu"),
+            features: string("features"),
+            data_layout: string("data-layout"),
+        })
+    }
+
+    /// Translates the specification to a zig target and a CPU model with the features of the
+    /// specification applied.
+    pub(crate) fn translate(&self) -> Result<(String, Cpu), String> {
+        let zig_target = guess_target_triple(&self.llvm_target)
+            .ok_or_else(|| format!("can not translate llvm-target `{}`", self.llvm_target))?;
+        let mut parts = zig_target
+            .split('-')
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let arch = parts[0].clone();
+
+        // The endianness of the architecture is dictated by the data layout.
+        let arch = match self
+            .data_layout
+            .as_deref()
+            .and_then(|l| l.split('-').next())
+        {
+            Some("E") => big_endian_arch(&arch).unwrap_or(&arch).to_string(),
+            Some("e") => little_endian_arch(&arch).unwrap_or(&arch).to_string(),
+            _ => arch,
+        };
+        let rust_arch = &*self.arch;
+        if !compatible_arch(rust_arch, &arch) {
+            return Err(format!(
+                "arch `{rust_arch}` does not match llvm-target `{}`",
+                self.llvm_target
+            ));
+        }
+        parts[0] = arch.clone();
+
+        if let Some(os) = self.os.as_deref() {
+            parts[1] = match os {
+                "none" => "freestanding".to_string(),
+                os => os.to_string(),
+            };
+        }
+        match (parts.get(2), self.env.as_deref()) {
+            (None, Some(env)) if !env.is_empty() => parts.push(env.to_string()),
+            _ => {}
+        }
+
+        let mut cpu = match &self.cpu {
+            Some(cpu) => Cpu::new(translate_arch_cpu(&arch, cpu)),
+            None => Cpu::default(),
+        };
+        for feature in self.features.iter().flat_map(|f| f.split(',')) {
+            if let Some(feature) = feature.strip_prefix('+') {
+                if let Some(feature) = translate_arch_feature(&arch, feature) {
+                    cpu.add(feature);
+                }
+            } else if let Some(feature) = feature.strip_prefix('-') {
+                if let Some(feature) = translate_arch_feature(&arch, feature) {
+                    cpu.remove(feature);
+                }
+            }
+        }
+
+        Ok((parts.join("-"), cpu))
+    }
+}
+
+/// Checks whether the zig architecture `zig_arch` is a variant of the Rust architecture
+/// `rust_arch`, e.g. `thumb` of `arm` or `mips64el` of `mips64`.
+fn compatible_arch(rust_arch: &str, zig_arch: &str) -> bool {
+    rust_arch == zig_arch
+        || match rust_arch {
+            "arm" => matches!(zig_arch, "armeb" | "thumb" | "thumbeb"),
+            "aarch64" => zig_arch == "aarch64_be",
+            "mips" => zig_arch == "mipsel",
+            "mips64" => zig_arch == "mips64el",
+            "powerpc" => zig_arch == "powerpcle",
+            "powerpc64" => zig_arch == "powerpc64le",
+            "bpf" => matches!(zig_arch, "bpfel" | "bpfeb"),
+            _ => false,
+        }
+}
+
+fn big_endian_arch(arch: &str) -> Option<&'static str> {
+    match arch {
+        "aarch64" => Some("aarch64_be"),
+        "arm" => Some("armeb"),
+        "thumb" => Some("thumbeb"),
+        "mipsel" => Some("mips"),
+        "mips64el" => Some("mips64"),
+        "powerpc64le" => Some("powerpc64"),
+        "bpfel" => Some("bpfeb"),
+        _ => None,
+    }
+}
+
+fn little_endian_arch(arch: &str) -> Option<&'static str> {
+    match arch {
+        "aarch64_be" => Some("aarch64"),
+        "armeb" => Some("arm"),
+        "thumbeb" => Some("thumb"),
+        "mips" => Some("mipsel"),
+        "mips64" => Some("mips64el"),
+        "powerpc64" => Some("powerpc64le"),
+        "bpfeb" => Some("bpfel"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_kernel_spec() {
+        let spec = TargetSpec::parse(
+            r#"{
+                "llvm-target": "x86_64-unknown-none",
+                "data-layout": "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128",
+                "arch": "x86_64",
+                "target-endian": "little",
+                "target-pointer-width": "64",
+                "os": "none",
+                "executables": true,
+                "features": "-mmx,-sse,+soft-float",
+                "panic-strategy": "abort"
+            }"#,
+        )
+        .unwrap();
+        let (target, cpu) = spec.translate().unwrap();
+        assert_eq!(target, "x86_64-freestanding");
+        assert_eq!(cpu.to_string(), "baseline+soft_float-mmx-sse");
+    }
+
+    #[test]
+    fn find_spec() {
+        let dir = env::temp_dir().join(format!("zigcli-target-spec-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("x86_64-kernel.json");
+        fs::write(&path, "{}").unwrap();
+
+        let search_path = env::join_paths([Path::new("missing"), &dir]).unwrap();
+        assert_eq!(
+            TargetSpec::find("x86_64-kernel", Some(&search_path)),
+            Some(path.clone())
+        );
+        assert_eq!(
+            TargetSpec::find(path.to_str().unwrap(), None),
+            Some(path.clone())
+        );
+        assert_eq!(TargetSpec::find("x86_64-kernel", None), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reject_mismatched_arch() {
+        let spec =
+            TargetSpec::parse(r#"{ "llvm-target": "aarch64-unknown-none", "arch": "riscv64" }"#)
+                .unwrap();
+        assert!(spec.translate().is_err());
+
+        for (llvm_target, arch) in [
+            ("x86_64-unknown-none", "x86"),
+            ("powerpc64-unknown-linux-gnu", "powerpc"),
+            ("mips64-unknown-linux-gnuabi64", "mips"),
+        ] {
+            let spec = TargetSpec::parse(&format!(
+                r#"{{ "llvm-target": "{llvm_target}", "arch": "{arch}" }}"#
+            ))
+            .unwrap();
+            assert!(spec.translate().is_err(), "{llvm_target} is not {arch}");
+        }
+
+        let spec = TargetSpec::parse(r#"{ "llvm-target": "thumbv7em-none-eabi", "arch": "arm" }"#)
+            .unwrap();
+        assert!(spec.translate().is_ok());
+    }
+}
@@ -0,0 +1,553 @@
+//! Minimal TOML reader, sufficient for reading the metadata of `Cargo.toml` manifests.
+
+use std::collections::BTreeMap;
+
+/// A parsed TOML value.
+///
+/// Dates and times are kept as strings.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Parses a TOML document into a table.
+    pub(crate) fn parse(s: &str) -> Result<Value, String> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+            line: 1,
+        };
+        parser
+            .document()
+            .map_err(|e| format!("line {}: {}", parser.line, e))
+    }
+
+    /// Returns the value at the dotted key `path`, e.g. `package.metadata` or
+    /// `target."cfg(unix)".dependencies`.
+    ///
+    /// Returns `None` if `path` is not a valid key.
+    pub(crate) fn get_path(&self, path: &str) -> Option<&Value> {
+        let mut parser = Parser {
+            chars: path.chars().collect(),
+            pos: 0,
+            line: 1,
+        };
+        let keys = parser.key().ok()?;
+        if parser.peek().is_some() {
+            return None;
+        }
+        keys.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    /// Returns the member `key` of a table.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Table(table) => table.get(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a string slice, if it is a string.
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_str(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{expected}`, found `{c}`")),
+            None => Err(format!("expected `{expected}`, found end of input")),
+        }
+    }
+
+    /// Skips spaces, tabs and comments on the current line.
+    fn skip_inline_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' => {
+                    self.next();
+                }
+                '#' => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.next();
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines and comments.
+    fn skip_whitespace(&mut self) {
+        loop {
+            self.skip_inline_whitespace();
+            match self.peek() {
+                Some('\n' | '\r') => {
+                    self.next();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_inline_whitespace();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => Ok(()),
+            Some(c) => Err(format!("unexpected character `{c}`")),
+        }
+    }
+
+    fn document(&mut self) -> Result<Value, String> {
+        let mut root = Value::Table(BTreeMap::new());
+        let mut current = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') if self.peek_str("[[") => {
+                    self.pos += 2;
+                    let path = self.key()?;
+                    self.expect(']')?;
+                    self.expect(']')?;
+                    self.end_of_line()?;
+                    let (last, parent) = path.split_last().unwrap();
+                    let parent = table_at(&mut root, parent)?;
+                    let array = parent
+                        .entry(last.clone())
+                        .or_insert_with(|| Value::Array(vec![]));
+                    match array {
+                        Value::Array(values) => values.push(Value::Table(BTreeMap::new())),
+                        _ => return Err(format!("`{last}` is not an array of tables")),
+                    }
+                    current = path;
+                }
+                Some('[') => {
+                    self.next();
+                    let path = self.key()?;
+                    self.expect(']')?;
+                    self.end_of_line()?;
+                    table_at(&mut root, &path)?;
+                    current = path;
+                }
+                Some(_) => {
+                    let (path, value) = self.key_value()?;
+                    self.end_of_line()?;
+                    let table = table_at(&mut root, &current)?;
+                    insert(table, &path, value)?;
+                }
+            }
+        }
+    }
+
+    fn key_value(&mut self) -> Result<(Vec<String>, Value), String> {
+        let path = self.key()?;
+        self.expect('=')?;
+        self.skip_inline_whitespace();
+        let value = self.value()?;
+        Ok((path, value))
+    }
+
+    /// Parses a possibly dotted key.
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut path = vec![];
+        loop {
+            self.skip_inline_whitespace();
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let mut key = String::new();
+                    while let Some(c) = self.peek() {
+                        if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                            break;
+                        }
+                        key.push(c);
+                        self.next();
+                    }
+                    if key.is_empty() {
+                        return Err("expected a key".to_string());
+                    }
+                    key
+                }
+            };
+            path.push(key);
+            self.skip_inline_whitespace();
+            if self.peek() == Some('.') {
+                self.next();
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') if self.peek_str("\"\"\"") => {
+                self.multiline_basic_string().map(Value::String)
+            }
+            Some('\'') if self.peek_str("'''") => {
+                self.multiline_literal_string().map(Value::String)
+            }
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) if self.peek_str("true") => {
+                self.pos += 4;
+                Ok(Value::Boolean(true))
+            }
+            Some(_) if self.peek_str("false") => {
+                self.pos += 5;
+                Ok(Value::Boolean(false))
+            }
+            Some(_) => self.scalar(),
+            None => Err("expected a value".to_string()),
+        }
+    }
+
+    /// Parses numbers, dates and times.
+    fn scalar(&mut self) -> Result<Value, String> {
+        let mut raw = String::new();
+        while let Some(c) = self.peek() {
+            let date_time_space = c == ' '
+                && raw.len() == 10
+                && self
+                    .chars
+                    .get(self.pos + 1)
+                    .is_some_and(char::is_ascii_digit);
+            if !(c.is_ascii_alphanumeric() || "+-_.:".contains(c) || date_time_space) {
+                break;
+            }
+            raw.push(c);
+            self.next();
+        }
+
+        let number = raw.replace('_', "");
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(digits) => (-1, digits),
+            None => (1, number.strip_prefix('+').unwrap_or(&number)),
+        };
+        let radix = [("0x", 16), ("0o", 8), ("0b", 2)]
+            .into_iter()
+            .find_map(|(prefix, radix)| Some((digits.strip_prefix(prefix)?, radix)));
+        if let Some((digits, radix)) = radix {
+            return i64::from_str_radix(digits, radix)
+                .map(|i| Value::Integer(sign * i))
+                .map_err(|_| format!("invalid integer `{raw}`"));
+        }
+        if let Ok(i) = number.parse() {
+            return Ok(Value::Integer(i));
+        }
+        match digits {
+            "inf" => return Ok(Value::Float(sign as f64 * f64::INFINITY)),
+            "nan" => return Ok(Value::Float(f64::NAN)),
+            _ => {}
+        }
+        if let Ok(f) = number.parse() {
+            return Ok(Value::Float(f));
+        }
+        if raw.len() >= 8 && raw.contains([':', '-']) {
+            return Ok(Value::String(raw));
+        }
+        Err(format!("invalid value `{raw}`"))
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let hex = |parser: &mut Parser, len: usize| {
+            let mut code = 0;
+            for _ in 0..len {
+                let digit = parser
+                    .next()
+                    .and_then(|c| c.to_digit(16))
+                    .ok_or_else(|| "invalid unicode escape".to_string())?;
+                code = code * 16 + digit;
+            }
+            char::from_u32(code).ok_or_else(|| "invalid unicode escape".to_string())
+        };
+        match self.next() {
+            Some('b') => Ok('\u{8}'),
+            Some('t') => Ok('\t'),
+            Some('n') => Ok('\n'),
+            Some('f') => Ok('\u{c}'),
+            Some('r') => Ok('\r'),
+            Some('e') => Ok('\u{1b}'),
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('u') => hex(self, 4),
+            Some('U') => hex(self, 8),
+            Some(c) => Err(format!("invalid escape `\\{c}`")),
+            None => Err("unterminated string".to_string()),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(self.escape()?),
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(string),
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn multiline_basic_string(&mut self) -> Result<String, String> {
+        self.pos += 3;
+        self.skip_leading_newline();
+        let mut string = String::new();
+        loop {
+            if self.peek_str("\"\"\"") && !self.peek_str("\"\"\"\"") {
+                self.pos += 3;
+                return Ok(string);
+            }
+            match self.next() {
+                Some('\\') => {
+                    // A line ending backslash trims the following whitespace.
+                    if matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                        while matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                            self.next();
+                        }
+                    } else {
+                        string.push(self.escape()?);
+                    }
+                }
+                Some(c) => string.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn multiline_literal_string(&mut self) -> Result<String, String> {
+        self.pos += 3;
+        self.skip_leading_newline();
+        let mut string = String::new();
+        loop {
+            if self.peek_str("'''") && !self.peek_str("''''") {
+                self.pos += 3;
+                return Ok(string);
+            }
+            match self.next() {
+                Some(c) => string.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn skip_leading_newline(&mut self) {
+        if self.peek_str("\r\n") {
+            self.next();
+        }
+        if self.peek() == Some('\n') {
+            self.next();
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.next();
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return Err("expected `,` or `]` in array".to_string()),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut table = BTreeMap::new();
+        self.skip_inline_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Value::Table(table));
+        }
+        loop {
+            let (path, value) = self.key_value()?;
+            insert(&mut table, &path, value)?;
+            self.skip_inline_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return Err("expected `,` or `}` in inline table".to_string()),
+            }
+        }
+    }
+}
+
+/// Returns the table at `path`, creating missing tables along the way.
+///
+/// Arrays of tables resolve to their last element.
+fn table_at<'a>(
+    root: &'a mut Value,
+    path: &[String],
+) -> Result<&'a mut BTreeMap<String, Value>, String> {
+    let mut value = root;
+    for key in path {
+        let table = match value {
+            Value::Table(table) => table,
+            _ => return Err(format!("`{key}` is not a table")),
+        };
+        value = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Table(BTreeMap::new()));
+        if let Value::Array(values) = value {
+            value = values
+                .last_mut()
+                .ok_or_else(|| format!("`{key}` is not a table"))?;
+        }
+    }
+    match value {
+        Value::Table(table) => Ok(table),
+        _ => Err("expected a table".to_string()),
+    }
+}
+
+fn insert(
+    table: &mut BTreeMap<String, Value>,
+    path: &[String],
+    value: Value,
+) -> Result<(), String> {
+    let (last, parent) = path.split_last().unwrap();
+    let mut parent_value = Value::Table(std::mem::take(table));
+    let result = table_at(&mut parent_value, parent).and_then(|parent| {
+        match parent.insert(last.clone(), value) {
+            None => Ok(()),
+            Some(_) => Err(format!("duplicate key `{last}`")),
+        }
+    });
+    if let Value::Table(parent_table) = parent_value {
+        *table = parent_table;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = Value::parse(
+            r#"
+            [package]
+            name = "foo" # comment
+            version = '0.1.0'
+            authors = [
+                "a",
+                "b",
+            ]
+
+            [target."cfg(unix)".dependencies]
+            libc = "0.2"
+
+            [package.metadata.zigcli]
+            path = "zig_package"
+            jobs = 0x10
+            options = { simd = true, level = 1_000 }
+            profiles.dist = "ReleaseSmall"
+
+            [[package.metadata.zigcli.packages]]
+            path = """\
+            first"""
+
+            [[package.metadata.zigcli.packages]]
+            path = 'second'
+            date = 1979-05-27T07:32:00Z
+            "#,
+        )
+        .unwrap();
+
+        let zigcli = manifest.get_path("package.metadata.zigcli").unwrap();
+        assert_eq!(zigcli.get("path").unwrap().as_str(), Some("zig_package"));
+        assert_eq!(zigcli.get("jobs"), Some(&Value::Integer(16)));
+        assert_eq!(
+            zigcli.get_path("options.level"),
+            Some(&Value::Integer(1000))
+        );
+        assert_eq!(zigcli.get_path("options.simd"), Some(&Value::Boolean(true)));
+        assert_eq!(
+            zigcli.get_path("profiles.dist").unwrap().as_str(),
+            Some("ReleaseSmall")
+        );
+        let Some(Value::Array(packages)) = zigcli.get("packages") else {
+            panic!("expected an array of tables");
+        };
+        assert_eq!(packages[0].get("path").unwrap().as_str(), Some("first"));
+        assert_eq!(packages[1].get("path").unwrap().as_str(), Some("second"));
+        assert_eq!(
+            manifest
+                .get_path(r#"target."cfg(unix)".dependencies.libc"#)
+                .unwrap()
+                .as_str(),
+            Some("0.2")
+        );
+        assert_eq!(manifest.get_path("target.cfg(unix)"), None);
+        assert!(matches!(
+            manifest.get_path("package.authors"),
+            Some(Value::Array(authors)) if authors.len() == 2
+        ));
+
+        assert!(Value::parse("a = 1\na = 2").is_err());
+        assert!(Value::parse("a = \"unterminated").is_err());
+    }
+}